@@ -0,0 +1,211 @@
+//! Lays the starting stretch of track with the game's own `generate_track_segments` in a
+//! headless app, timing it and counting the entities and mesh assets it leaves behind.
+//! The same stretch is then laid the way it was before the segments were baked (40 m
+//! segments of 4 m rail pieces and separate sleepers, each its own entity) and the two
+//! are printed side by side.
+//!
+//! These are entity counts, not draw calls: how entities turn into draws depends on the
+//! renderer's batching, which a headless app doesn't run.
+//!
+//! Run with `cargo bench --bench track_entities`.
+
+// Only the track code is exercised
+#![allow(dead_code)]
+
+// The game is a binary, so its modules are compiled straight into the benchmark
+#[path = "../src/game/mod.rs"]
+mod game;
+#[path = "../src/resources/mod.rs"]
+mod resources;
+
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use game::track::{TrackMeshes, TrackSegment, generate_track_segments};
+use game::track_mesh::{
+    RAIL_HEIGHT, RAIL_SPACING, RAIL_WIDTH, SEGMENT_LENGTH, SLEEPER_DEPTH, SLEEPER_HEIGHT,
+    SLEEPER_SPACING, SLEEPER_WIDTH,
+};
+use resources::lane_layout::LaneLayout;
+use resources::theme::Themes;
+use resources::track_pool::TrackPool;
+use std::time::{Duration, Instant};
+
+const RUNS: u32 = 50;
+
+// The layout before baking
+const UNBAKED_SEGMENT_LENGTH: f32 = 40.0;
+const UNBAKED_RAIL_PIECE_LENGTH: f32 = 4.0;
+
+struct Measurement {
+    segments: usize,
+    entities: u32,
+    meshes: usize,
+    spawn_time: Duration,
+}
+
+fn new_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>()
+        .init_resource::<TrackPool>()
+        .init_resource::<TrackMeshes>()
+        .init_resource::<LaneLayout>()
+        .init_resource::<Themes>();
+    app
+}
+
+/// Lays `length` metres of every lane as the track used to be built: each segment gets
+/// its own rail and sleeper meshes and materials, and every rail piece and sleeper is a
+/// child entity of it
+fn lay_unbaked_track(
+    In(length): In<f32>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    lane_layout: Res<LaneLayout>,
+    themes: Res<Themes>,
+) {
+    let segments = (length / UNBAKED_SEGMENT_LENGTH).ceil() as i32;
+    let rail_pieces = (UNBAKED_SEGMENT_LENGTH / UNBAKED_RAIL_PIECE_LENGTH) as i32;
+    let sleepers = (UNBAKED_SEGMENT_LENGTH / SLEEPER_SPACING) as i32;
+
+    for segment in 0..segments {
+        let z_position = segment as f32 * UNBAKED_SEGMENT_LENGTH;
+        let theme = themes.at(z_position);
+
+        for track_index in lane_layout.lanes() {
+            let rail_mesh = meshes.add(Cuboid::new(
+                RAIL_WIDTH,
+                RAIL_HEIGHT,
+                UNBAKED_RAIL_PIECE_LENGTH,
+            ));
+            let rail_material = materials.add(theme.rail);
+            let sleeper_mesh =
+                meshes.add(Cuboid::new(SLEEPER_WIDTH, SLEEPER_HEIGHT, SLEEPER_DEPTH));
+            let sleeper_material = materials.add(theme.sleeper);
+
+            commands
+                .spawn((
+                    TrackSegment {
+                        track_index,
+                        segment_id: segment as u32,
+                    },
+                    Transform::from_xyz(lane_layout.x_position(track_index), 0.0, z_position),
+                    Visibility::default(),
+                ))
+                .with_children(|parent| {
+                    let first_piece_z =
+                        -UNBAKED_SEGMENT_LENGTH / 2.0 + UNBAKED_RAIL_PIECE_LENGTH / 2.0;
+                    for side in [-1.0, 1.0] {
+                        for i in 0..rail_pieces {
+                            parent.spawn((
+                                Mesh3d(rail_mesh.clone()),
+                                MeshMaterial3d(rail_material.clone()),
+                                Transform::from_xyz(
+                                    side * RAIL_SPACING,
+                                    RAIL_HEIGHT / 2.0 + SLEEPER_HEIGHT,
+                                    first_piece_z + i as f32 * UNBAKED_RAIL_PIECE_LENGTH,
+                                ),
+                            ));
+                        }
+                    }
+
+                    let first_sleeper_z = -UNBAKED_SEGMENT_LENGTH / 2.0 + SLEEPER_SPACING / 2.0;
+                    for i in 0..sleepers {
+                        parent.spawn((
+                            Mesh3d(sleeper_mesh.clone()),
+                            MeshMaterial3d(sleeper_material.clone()),
+                            Transform::from_xyz(
+                                0.0,
+                                SLEEPER_HEIGHT / 2.0,
+                                first_sleeper_z + i as f32 * SLEEPER_SPACING,
+                            ),
+                        ));
+                    }
+                });
+        }
+    }
+}
+
+/// How the starting stretch gets laid on a fresh app
+#[derive(Clone, Copy)]
+enum Layout {
+    Baked,
+    Unbaked { length: f32 },
+}
+
+/// Lays the track once on a fresh app, returning the app and how long it took
+fn lay_track(layout: Layout) -> (App, Duration) {
+    let mut app = new_app();
+    let world = app.world_mut();
+    let start = Instant::now();
+    let laid = match layout {
+        Layout::Baked => world.run_system_once(generate_track_segments),
+        Layout::Unbaked { length } => world.run_system_once_with(lay_unbaked_track, length),
+    };
+    laid.expect("the track systems' resources are all present");
+    let spawn_time = start.elapsed();
+    (app, spawn_time)
+}
+
+fn measure(layout: Layout) -> Measurement {
+    let entities_before = new_app().world().entities().len();
+    let (mut app, mut spawn_time) = lay_track(layout);
+    let world = app.world_mut();
+
+    let segments = world
+        .query_filtered::<(), With<TrackSegment>>()
+        .iter(world)
+        .count();
+    let entities = world.entities().len() - entities_before;
+    let meshes = world.resource::<Assets<Mesh>>().len();
+
+    // Lay it again on fresh apps for a steadier time
+    for _ in 1..RUNS {
+        spawn_time += lay_track(layout).1;
+    }
+
+    Measurement {
+        segments,
+        entities,
+        meshes,
+        spawn_time: spawn_time / RUNS,
+    }
+}
+
+fn print_row(name: &str, measurement: &Measurement) {
+    println!(
+        "{:>8} {:>9} {:>9} {:>14.1} {:>7} {:>12.2?}",
+        name,
+        measurement.segments,
+        measurement.entities,
+        measurement.entities as f32 / measurement.segments.max(1) as f32,
+        measurement.meshes,
+        measurement.spawn_time
+    );
+}
+
+fn main() {
+    let lanes = LaneLayout::default().count;
+    let baked = measure(Layout::Baked);
+    // Cover the same stretch of every lane that the real system laid
+    let length = baked.segments as f32 / lanes as f32 * SEGMENT_LENGTH;
+    let unbaked = measure(Layout::Unbaked { length });
+
+    println!(
+        "Starting track ({length} m of {lanes} lanes), spawn time averaged over {RUNS} runs\n"
+    );
+    println!(
+        "{:>8} {:>9} {:>9} {:>14} {:>7} {:>12}",
+        "", "segments", "entities", "per segment", "meshes", "spawn time"
+    );
+    print_row("unbaked", &unbaked);
+    print_row("baked", &baked);
+    println!(
+        "\nBaking leaves {:.1}x fewer entities ({} fewer) in {:.1}x the spawn time",
+        unbaked.entities as f32 / baked.entities.max(1) as f32,
+        unbaked.entities.saturating_sub(baked.entities),
+        baked.spawn_time.as_secs_f32() / unbaked.spawn_time.as_secs_f32().max(f32::EPSILON),
+    );
+}
@@ -0,0 +1,482 @@
+use crate::resources::profile::PlayerProfile;
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Slide,
+    Hoverboard,
+    Restart,
+    DailyRun,
+    BuyHoverboard,
+    Settings,
+    Achievements,
+    MenuUp,
+    MenuDown,
+    Confirm,
+    Back,
+}
+
+impl Action {
+    pub const ALL: [Action; 14] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Jump,
+        Action::Slide,
+        Action::Hoverboard,
+        Action::Restart,
+        Action::DailyRun,
+        Action::BuyHoverboard,
+        Action::Settings,
+        Action::Achievements,
+        Action::MenuUp,
+        Action::MenuDown,
+        Action::Confirm,
+        Action::Back,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Jump => "Jump",
+            Action::Slide => "Slide",
+            Action::Hoverboard => "Hoverboard",
+            Action::Restart => "Restart",
+            Action::DailyRun => "Daily run",
+            Action::BuyHoverboard => "Buy hoverboard",
+            Action::Settings => "Settings",
+            Action::Achievements => "Achievements",
+            Action::MenuUp => "Menu up",
+            Action::MenuDown => "Menu down",
+            Action::Confirm => "Confirm",
+            Action::Back => "Back",
+        }
+    }
+
+    /// Name used for this action's line in the profile
+    fn profile_key(&self) -> &'static str {
+        match self {
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Jump => "jump",
+            Action::Slide => "slide",
+            Action::Hoverboard => "hoverboard",
+            Action::Restart => "restart",
+            Action::DailyRun => "daily_run",
+            Action::BuyHoverboard => "buy_hoverboard",
+            Action::Settings => "settings",
+            Action::Achievements => "achievements",
+            Action::MenuUp => "menu_up",
+            Action::MenuDown => "menu_down",
+            Action::Confirm => "confirm",
+            Action::Back => "back",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum StickDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl StickDirection {
+    const ALL: [StickDirection; 4] = [
+        StickDirection::Left,
+        StickDirection::Right,
+        StickDirection::Up,
+        StickDirection::Down,
+    ];
+
+    fn is_held(&self, stick: Vec2) -> bool {
+        match self {
+            StickDirection::Left => stick.x < -STICK_THRESHOLD,
+            StickDirection::Right => stick.x > STICK_THRESHOLD,
+            StickDirection::Up => stick.y > STICK_THRESHOLD,
+            StickDirection::Down => stick.y < -STICK_THRESHOLD,
+        }
+    }
+}
+
+// How far the left stick has to be pushed to count as a press
+const STICK_THRESHOLD: f32 = 0.5;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Binding {
+    Key(KeyCode),
+    Button(GamepadButton),
+    Stick(StickDirection), // Left stick flick
+}
+
+impl Binding {
+    fn to_text(self) -> String {
+        match self {
+            Binding::Key(key) => format!("Key:{key:?}"),
+            Binding::Button(button) => format!("Button:{button:?}"),
+            Binding::Stick(direction) => format!("Stick:{direction:?}"),
+        }
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        let (kind, name) = text.split_once(':')?;
+        match kind {
+            "Key" => BINDABLE_KEYS
+                .iter()
+                .find(|key| format!("{key:?}") == name)
+                .map(|key| Binding::Key(*key)),
+            "Button" => BINDABLE_BUTTONS
+                .iter()
+                .find(|button| format!("{button:?}") == name)
+                .map(|button| Binding::Button(*button)),
+            "Stick" => StickDirection::ALL
+                .iter()
+                .find(|direction| format!("{direction:?}") == name)
+                .map(|direction| Binding::Stick(*direction)),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Binding::Key(key) => format!("{key:?}"),
+            Binding::Button(button) => format!("Pad {button:?}"),
+            Binding::Stick(direction) => format!("Stick {direction:?}"),
+        }
+    }
+}
+
+/// Keys that can be bound and round-trip through the profile
+pub const BINDABLE_KEYS: [KeyCode; 50] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::AltLeft,
+];
+
+pub const BINDABLE_BUTTONS: [GamepadButton; 16] = [
+    GamepadButton::South,
+    GamepadButton::East,
+    GamepadButton::North,
+    GamepadButton::West,
+    GamepadButton::LeftTrigger,
+    GamepadButton::LeftTrigger2,
+    GamepadButton::RightTrigger,
+    GamepadButton::RightTrigger2,
+    GamepadButton::Select,
+    GamepadButton::Start,
+    GamepadButton::LeftThumb,
+    GamepadButton::RightThumb,
+    GamepadButton::DPadUp,
+    GamepadButton::DPadDown,
+    GamepadButton::DPadLeft,
+    GamepadButton::DPadRight,
+];
+
+/// Which keys, buttons and stick directions trigger each action
+#[derive(Resource)]
+pub struct ActionMap {
+    bindings: HashMap<Action, Vec<Binding>>,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        use Binding::{Button, Key, Stick};
+
+        let defaults: [(Action, Vec<Binding>); 14] = [
+            (
+                Action::MoveLeft,
+                vec![
+                    Key(KeyCode::ArrowLeft),
+                    Key(KeyCode::KeyA),
+                    Button(GamepadButton::DPadLeft),
+                    Stick(StickDirection::Left),
+                ],
+            ),
+            (
+                Action::MoveRight,
+                vec![
+                    Key(KeyCode::ArrowRight),
+                    Key(KeyCode::KeyD),
+                    Button(GamepadButton::DPadRight),
+                    Stick(StickDirection::Right),
+                ],
+            ),
+            (
+                Action::Jump,
+                vec![
+                    Key(KeyCode::Space),
+                    Key(KeyCode::ArrowUp),
+                    Key(KeyCode::KeyW),
+                    Button(GamepadButton::South),
+                    Button(GamepadButton::DPadUp),
+                    Stick(StickDirection::Up),
+                ],
+            ),
+            (
+                Action::Slide,
+                vec![
+                    Key(KeyCode::ArrowDown),
+                    Key(KeyCode::KeyS),
+                    Button(GamepadButton::West),
+                    Button(GamepadButton::DPadDown),
+                    Stick(StickDirection::Down),
+                ],
+            ),
+            (
+                Action::Hoverboard,
+                vec![Key(KeyCode::KeyB), Button(GamepadButton::North)],
+            ),
+            (
+                Action::Restart,
+                vec![
+                    Key(KeyCode::KeyR),
+                    Key(KeyCode::Space),
+                    Key(KeyCode::Enter),
+                    Button(GamepadButton::Start),
+                ],
+            ),
+            (
+                Action::DailyRun,
+                vec![Key(KeyCode::KeyY), Button(GamepadButton::LeftTrigger)],
+            ),
+            (
+                Action::BuyHoverboard,
+                vec![Key(KeyCode::KeyH), Button(GamepadButton::North)],
+            ),
+            (
+                Action::Settings,
+                vec![Key(KeyCode::Tab), Button(GamepadButton::Select)],
+            ),
+            (
+                Action::Achievements,
+                vec![Key(KeyCode::KeyG), Button(GamepadButton::RightTrigger)],
+            ),
+            (
+                Action::MenuUp,
+                vec![
+                    Key(KeyCode::ArrowUp),
+                    Key(KeyCode::KeyW),
+                    Button(GamepadButton::DPadUp),
+                    Stick(StickDirection::Up),
+                ],
+            ),
+            (
+                Action::MenuDown,
+                vec![
+                    Key(KeyCode::ArrowDown),
+                    Key(KeyCode::KeyS),
+                    Button(GamepadButton::DPadDown),
+                    Stick(StickDirection::Down),
+                ],
+            ),
+            (
+                Action::Confirm,
+                vec![Key(KeyCode::Enter), Button(GamepadButton::South)],
+            ),
+            (
+                Action::Back,
+                vec![Key(KeyCode::Escape), Button(GamepadButton::East)],
+            ),
+        ];
+
+        Self {
+            bindings: defaults.into_iter().collect(),
+        }
+    }
+}
+
+impl ActionMap {
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Short hint for on-screen prompts: the first key and first gamepad button bound
+    pub fn describe(&self, action: Action) -> String {
+        let bindings = self.bindings(action);
+        let key = bindings
+            .iter()
+            .find(|binding| matches!(binding, Binding::Key(_)));
+        let button = bindings
+            .iter()
+            .find(|binding| matches!(binding, Binding::Button(_)));
+        key.into_iter()
+            .chain(button)
+            .map(Binding::label)
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+
+    /// Replaces the first binding of the same kind (key, button or stick), or adds one
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        let bindings = self.bindings.entry(action).or_default();
+        let same_kind = bindings.iter().position(|existing| {
+            std::mem::discriminant(existing) == std::mem::discriminant(&binding)
+        });
+        match same_kind {
+            Some(index) => bindings[index] = binding,
+            None => bindings.push(binding),
+        }
+    }
+
+    /// Defaults overlaid with any bindings saved in the profile
+    pub fn from_profile(profile: &PlayerProfile) -> Self {
+        let mut action_map = Self::default();
+        for action in Action::ALL {
+            let Some(saved) = profile.binding(action.profile_key()) else {
+                continue;
+            };
+            let bindings: Vec<Binding> = saved.split(',').filter_map(Binding::from_text).collect();
+            if !bindings.is_empty() {
+                action_map.bindings.insert(action, bindings);
+            }
+        }
+        action_map
+    }
+
+    pub fn save_to_profile(&self, profile: &mut PlayerProfile) {
+        for action in Action::ALL {
+            let saved = self
+                .bindings(action)
+                .iter()
+                .map(|binding| binding.to_text())
+                .collect::<Vec<_>>()
+                .join(",");
+            profile.set_binding(action.profile_key(), saved);
+        }
+        profile.save();
+    }
+}
+
+/// Per-frame view of which actions are held or were just pressed, from every device
+#[derive(Resource, Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    sticks_held: HashSet<StickDirection>, // Last frame's stick directions, for edge detection
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    /// Presses `action` this frame from a source outside the action map, like a swipe
+    pub fn press(&mut self, action: Action) {
+        self.pressed.insert(action);
+        self.just_pressed.insert(action);
+    }
+
+    /// Keeps `action` held without a fresh press
+    pub fn hold(&mut self, action: Action) {
+        self.pressed.insert(action);
+    }
+}
+
+pub fn load_action_map(mut commands: Commands, profile: Res<PlayerProfile>) {
+    commands.insert_resource(ActionMap::from_profile(&profile));
+}
+
+pub fn update_action_state(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    action_map: Res<ActionMap>,
+    mut action_state: ResMut<ActionState>,
+) {
+    let sticks_held: HashSet<StickDirection> = StickDirection::ALL
+        .into_iter()
+        .filter(|direction| {
+            gamepads
+                .iter()
+                .any(|gamepad| direction.is_held(gamepad.left_stick()))
+        })
+        .collect();
+
+    let mut pressed = HashSet::default();
+    let mut just_pressed = HashSet::default();
+
+    for action in Action::ALL {
+        for binding in action_map.bindings(action) {
+            let (held, fresh) = match *binding {
+                Binding::Key(key) => (
+                    keyboard_input.pressed(key),
+                    keyboard_input.just_pressed(key),
+                ),
+                Binding::Button(button) => (
+                    gamepads.iter().any(|gamepad| gamepad.pressed(button)),
+                    gamepads.iter().any(|gamepad| gamepad.just_pressed(button)),
+                ),
+                Binding::Stick(direction) => (
+                    sticks_held.contains(&direction),
+                    sticks_held.contains(&direction)
+                        && !action_state.sticks_held.contains(&direction),
+                ),
+            };
+            if held {
+                pressed.insert(action);
+            }
+            if fresh {
+                just_pressed.insert(action);
+            }
+        }
+    }
+
+    action_state.pressed = pressed;
+    action_state.just_pressed = just_pressed;
+    action_state.sticks_held = sticks_held;
+}
@@ -0,0 +1,599 @@
+use crate::game::events::{CoinCollected, Jumped, LaneChanged, PlayerDied, Slid, Stumbled};
+use crate::game::hoverboard::Hoverboard;
+use crate::game::player::Player;
+use crate::game::signal::TrainHorn;
+use crate::game::train::Train;
+use crate::resources::profile::PlayerProfile;
+use bevy::audio::{Source, SpatialScale, Volume};
+use bevy::prelude::*;
+use std::time::Duration;
+
+const SAMPLE_RATE: u32 = 44_100;
+// Fade at either end of held tones, so they start and stop without a click
+const FADE_SAMPLES: u32 = 220;
+// Keeps a full-scale waveform from clipping once several sounds overlap
+const PEAK: f32 = 0.4;
+// Shrinks world distances for spatial sounds, which fall off with the square of distance
+const TRAIN_SPATIAL_SCALE: f32 = 0.12;
+// Step for the volume sliders on the settings screen
+const VOLUME_STEP: f32 = 0.1;
+
+/// Shape of one cycle of a synthesized tone
+#[derive(Clone, Copy, Debug)]
+pub enum Waveform {
+    Sine,
+    Square,
+    Saw,
+}
+
+impl Waveform {
+    /// Sample at `phase`, a fraction of one cycle
+    fn sample(self, phase: f32) -> f32 {
+        match self {
+            Waveform::Sine => (phase * std::f32::consts::TAU).sin(),
+            Waveform::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Saw => phase * 2.0 - 1.0,
+        }
+    }
+}
+
+/// How a synthesized tone's volume moves over its length
+#[derive(Clone, Copy, Debug)]
+pub enum Envelope {
+    Pluck, // Fades out over the whole sound
+    Hold,  // Full volume, with short fades at either end
+    Loop,  // Full volume throughout, so it repeats seamlessly
+}
+
+/// Short procedural sound: a tone sweeping between two pitches with some noise mixed in.
+///
+/// The game ships no audio files, so every effect is one of these.
+#[derive(Asset, TypePath, Clone, Copy, Debug)]
+pub struct Synth {
+    pub waveform: Waveform,
+    pub start_frequency: f32,
+    pub end_frequency: f32,
+    pub noise: f32, // Share of white noise, from 0 (pure tone) to 1
+    pub envelope: Envelope,
+    pub duration: Duration,
+}
+
+impl Decodable for Synth {
+    type DecoderItem = f32;
+    type Decoder = SynthDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        SynthDecoder {
+            synth: *self,
+            sample: 0,
+            total: (self.duration.as_secs_f32() * SAMPLE_RATE as f32) as u32,
+            phase: 0.0,
+            noise_state: 0x9E37_79B9,
+        }
+    }
+}
+
+/// Renders a [`Synth`] one mono sample at a time
+pub struct SynthDecoder {
+    synth: Synth,
+    sample: u32,
+    total: u32,
+    phase: f32,
+    noise_state: u32,
+}
+
+impl SynthDecoder {
+    /// Xorshift white noise between -1 and 1
+    fn noise(&mut self) -> f32 {
+        self.noise_state ^= self.noise_state << 13;
+        self.noise_state ^= self.noise_state >> 17;
+        self.noise_state ^= self.noise_state << 5;
+        self.noise_state as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+
+    fn envelope(&self) -> f32 {
+        let remaining = self.total - self.sample;
+        match self.synth.envelope {
+            Envelope::Pluck => {
+                let attack = (self.sample as f32 / FADE_SAMPLES as f32).min(1.0);
+                attack * remaining as f32 / self.total as f32
+            }
+            Envelope::Hold => {
+                let edge = self.sample.min(remaining);
+                (edge as f32 / FADE_SAMPLES as f32).min(1.0)
+            }
+            Envelope::Loop => 1.0,
+        }
+    }
+}
+
+impl Iterator for SynthDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.sample >= self.total {
+            return None;
+        }
+
+        let progress = self.sample as f32 / self.total as f32;
+        let frequency = self.synth.start_frequency
+            + (self.synth.end_frequency - self.synth.start_frequency) * progress;
+        self.phase = (self.phase + frequency / SAMPLE_RATE as f32).fract();
+
+        let tone = self.synth.waveform.sample(self.phase);
+        let noise = self.noise();
+        let mix = tone * (1.0 - self.synth.noise) + noise * self.synth.noise;
+        let value = mix * self.envelope() * PEAK;
+
+        self.sample += 1;
+        Some(value)
+    }
+}
+
+impl Source for SynthDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some((self.total - self.sample) as usize)
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(self.synth.duration)
+    }
+}
+
+/// Volume setting that scales a sound, on top of the master volume
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum VolumeCategory {
+    Master,
+    Music,
+    Sfx,
+}
+
+impl VolumeCategory {
+    pub const ALL: [VolumeCategory; 3] = [
+        VolumeCategory::Master,
+        VolumeCategory::Music,
+        VolumeCategory::Sfx,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            VolumeCategory::Master => "Master volume",
+            VolumeCategory::Music => "Music volume",
+            VolumeCategory::Sfx => "Effects volume",
+        }
+    }
+
+    /// Level set for this category alone, from 0 to 1
+    pub fn level(&self, profile: &PlayerProfile) -> f32 {
+        match self {
+            VolumeCategory::Master => profile.master_volume,
+            VolumeCategory::Music => profile.music_volume,
+            VolumeCategory::Sfx => profile.sfx_volume,
+        }
+    }
+
+    /// Raises or lowers this category's level by `steps` slider steps
+    pub fn step(&self, profile: &mut PlayerProfile, steps: i32) {
+        let level = (self.level(profile) + steps as f32 * VOLUME_STEP).clamp(0.0, 1.0);
+        // Rounded to whole steps so the profile doesn't collect float noise
+        let level = (level / VOLUME_STEP).round() * VOLUME_STEP;
+        match self {
+            VolumeCategory::Master => profile.master_volume = level,
+            VolumeCategory::Music => profile.music_volume = level,
+            VolumeCategory::Sfx => profile.sfx_volume = level,
+        }
+    }
+
+    /// What a sound in this category is actually played at, master volume included
+    pub fn gain(&self, profile: &PlayerProfile) -> f32 {
+        match self {
+            VolumeCategory::Master => profile.master_volume,
+            _ => profile.master_volume * self.level(profile),
+        }
+    }
+}
+
+/// Sound the game wants played, derived from what happens in a run.
+///
+/// Gameplay systems never play audio themselves; the cue systems below turn gameplay
+/// events into these, so the stream can be read without an audio device.
+#[derive(Message, Clone, Copy, PartialEq, Debug)]
+pub enum SoundCue {
+    Jump,
+    Slide,
+    LaneSwitch,
+    Coin,
+    PowerUp,
+    Stumble,
+    Crash,
+    Horn { train: Entity, express: bool },
+}
+
+/// Loaded synth for every sound effect. Only present when the app has audio, so without
+/// it cues are still sent but nothing plays.
+#[derive(Resource)]
+pub struct SoundBank {
+    jump: Handle<Synth>,
+    slide: Handle<Synth>,
+    lane_switch: Handle<Synth>,
+    coin: Handle<Synth>,
+    power_up: Handle<Synth>,
+    stumble: Handle<Synth>,
+    crash: Handle<Synth>,
+    horn: Handle<Synth>,
+    rumble: Handle<Synth>,
+}
+
+/// Looping sound whose sink follows the volume settings while it plays
+#[derive(Component)]
+pub struct LoopingSound {
+    pub category: VolumeCategory,
+    pub gain: f32, // The sound's own level, before the volume settings
+}
+
+fn synth(
+    waveform: Waveform,
+    frequencies: (f32, f32),
+    noise: f32,
+    envelope: Envelope,
+    seconds: f32,
+) -> Synth {
+    Synth {
+        waveform,
+        start_frequency: frequencies.0,
+        end_frequency: frequencies.1,
+        noise,
+        envelope,
+        duration: Duration::from_secs_f32(seconds),
+    }
+}
+
+/// Builds the sound effects, if the app was set up with audio
+pub fn load_sound_bank(mut commands: Commands, synths: Option<ResMut<Assets<Synth>>>) {
+    let Some(mut synths) = synths else {
+        return;
+    };
+
+    commands.insert_resource(SoundBank {
+        jump: synths.add(synth(
+            Waveform::Sine,
+            (300.0, 700.0),
+            0.0,
+            Envelope::Pluck,
+            0.15,
+        )),
+        slide: synths.add(synth(
+            Waveform::Sine,
+            (200.0, 120.0),
+            0.8,
+            Envelope::Pluck,
+            0.3,
+        )),
+        lane_switch: synths.add(synth(
+            Waveform::Sine,
+            (500.0, 350.0),
+            0.6,
+            Envelope::Pluck,
+            0.12,
+        )),
+        coin: synths.add(synth(
+            Waveform::Square,
+            (988.0, 1319.0),
+            0.0,
+            Envelope::Pluck,
+            0.12,
+        )),
+        power_up: synths.add(synth(
+            Waveform::Saw,
+            (400.0, 1200.0),
+            0.0,
+            Envelope::Hold,
+            0.5,
+        )),
+        stumble: synths.add(synth(
+            Waveform::Square,
+            (220.0, 110.0),
+            0.2,
+            Envelope::Pluck,
+            0.25,
+        )),
+        crash: synths.add(synth(
+            Waveform::Sine,
+            (120.0, 40.0),
+            0.9,
+            Envelope::Pluck,
+            0.8,
+        )),
+        horn: synths.add(synth(
+            Waveform::Square,
+            (311.0, 311.0),
+            0.05,
+            Envelope::Hold,
+            0.9,
+        )),
+        // A whole number of 55 Hz cycles, so the loop has no seam
+        rumble: synths.add(synth(Waveform::Saw, (55.0, 55.0), 0.7, Envelope::Loop, 1.0)),
+    });
+}
+
+/// Cues the sounds of the run's gameplay events
+pub fn cue_gameplay_sounds(
+    mut jumps: MessageReader<Jumped>,
+    mut slides: MessageReader<Slid>,
+    mut lane_changes: MessageReader<LaneChanged>,
+    mut coins: MessageReader<CoinCollected>,
+    mut stumbles: MessageReader<Stumbled>,
+    mut deaths: MessageReader<PlayerDied>,
+    mut cues: MessageWriter<SoundCue>,
+) {
+    let events = [
+        (jumps.read().count(), SoundCue::Jump),
+        (slides.read().count(), SoundCue::Slide),
+        (lane_changes.read().count(), SoundCue::LaneSwitch),
+        (coins.read().count(), SoundCue::Coin),
+        (stumbles.read().count(), SoundCue::Stumble),
+        // Two hits in the same frame are still one crash
+        (deaths.read().count().min(1), SoundCue::Crash),
+    ];
+    for (count, cue) in events {
+        for _ in 0..count {
+            cues.write(cue);
+        }
+    }
+}
+
+/// Cues hoverboards as they are ridden
+pub fn cue_power_ups(
+    power_up_query: Query<(), (With<Player>, Added<Hoverboard>)>,
+    mut cues: MessageWriter<SoundCue>,
+) {
+    if !power_up_query.is_empty() {
+        cues.write(SoundCue::PowerUp);
+    }
+}
+
+pub fn cue_train_horns(mut horns: MessageReader<TrainHorn>, mut cues: MessageWriter<SoundCue>) {
+    for horn in horns.read() {
+        cues.write(SoundCue::Horn {
+            train: horn.train,
+            express: horn.express,
+        });
+    }
+}
+
+/// Plays every cue sent this frame. Horns sound from their train as it passes.
+pub fn play_sound_cues(
+    mut commands: Commands,
+    mut cues: MessageReader<SoundCue>,
+    sound_bank: Option<Res<SoundBank>>,
+    profile: Res<PlayerProfile>,
+    train_query: Query<(), With<Train>>,
+) {
+    let Some(sound_bank) = sound_bank else {
+        cues.clear();
+        return;
+    };
+    let gain = VolumeCategory::Sfx.gain(&profile);
+
+    for cue in cues.read() {
+        let sound = match *cue {
+            SoundCue::Jump => &sound_bank.jump,
+            SoundCue::Slide => &sound_bank.slide,
+            SoundCue::LaneSwitch => &sound_bank.lane_switch,
+            SoundCue::Coin => &sound_bank.coin,
+            SoundCue::PowerUp => &sound_bank.power_up,
+            SoundCue::Stumble => &sound_bank.stumble,
+            SoundCue::Crash => &sound_bank.crash,
+            SoundCue::Horn { train, express } => {
+                // The train may have been recycled before the cue was read
+                if train_query.contains(train) {
+                    commands.spawn((
+                        AudioPlayer(sound_bank.horn.clone()),
+                        PlaybackSettings::DESPAWN
+                            .with_volume(Volume::Linear(gain))
+                            .with_speed(if express { 1.25 } else { 1.0 })
+                            .with_spatial(true)
+                            .with_spatial_scale(SpatialScale::new(TRAIN_SPATIAL_SCALE)),
+                        Transform::default(),
+                        ChildOf(train),
+                    ));
+                }
+                continue;
+            }
+        };
+
+        commands.spawn((
+            AudioPlayer(sound.clone()),
+            PlaybackSettings::DESPAWN.with_volume(Volume::Linear(gain)),
+        ));
+    }
+}
+
+/// Gives each moving train a rumble that pans and swells as it passes the player
+pub fn attach_train_rumble(
+    mut commands: Commands,
+    train_query: Query<(Entity, &Train), Added<Train>>,
+    sound_bank: Option<Res<SoundBank>>,
+    profile: Res<PlayerProfile>,
+) {
+    let Some(sound_bank) = sound_bank else {
+        return;
+    };
+
+    for (entity, train) in train_query.iter() {
+        if !train.train_type.is_moving() {
+            continue;
+        }
+        let looping = LoopingSound {
+            category: VolumeCategory::Sfx,
+            gain: 0.6,
+        };
+        let volume = looping.gain * looping.category.gain(&profile);
+        commands.spawn((
+            looping,
+            AudioPlayer(sound_bank.rumble.clone()),
+            PlaybackSettings::LOOP
+                .with_volume(Volume::Linear(volume))
+                .with_spatial(true)
+                .with_spatial_scale(SpatialScale::new(TRAIN_SPATIAL_SCALE)),
+            Transform::default(),
+            ChildOf(entity),
+        ));
+    }
+}
+
+/// Carries volume changes from the settings screen over to sounds that are still playing
+pub fn apply_volume_levels(
+    profile: Res<PlayerProfile>,
+    mut sink_query: Query<(&LoopingSound, &mut SpatialAudioSink)>,
+) {
+    if !profile.is_changed() {
+        return;
+    }
+    for (looping, mut sink) in sink_query.iter_mut() {
+        sink.set_volume(Volume::Linear(
+            looping.gain * looping.category.gain(&profile),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::collision::DeathCause;
+    use crate::game::events::Cause;
+    use std::path::PathBuf;
+
+    fn profile(master: f32, music: f32, sfx: f32) -> PlayerProfile {
+        let mut profile = PlayerProfile::load(PathBuf::from("no-such-profile.txt"));
+        profile.master_volume = master;
+        profile.music_volume = music;
+        profile.sfx_volume = sfx;
+        profile
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-5,
+            "{actual} is not {expected}"
+        );
+    }
+
+    #[test]
+    fn categories_play_at_their_level_times_master() {
+        let profile = profile(0.5, 0.8, 0.4);
+        assert_near(VolumeCategory::Master.gain(&profile), 0.5);
+        assert_near(VolumeCategory::Music.gain(&profile), 0.4);
+        assert_near(VolumeCategory::Sfx.gain(&profile), 0.2);
+    }
+
+    #[test]
+    fn steps_round_to_the_slider_and_stay_in_range() {
+        let mut profile = profile(1.0, 0.5, 0.33);
+        VolumeCategory::Sfx.step(&mut profile, 1);
+        assert_near(profile.sfx_volume, 0.4);
+
+        VolumeCategory::Music.step(&mut profile, -10);
+        assert_near(profile.music_volume, 0.0);
+
+        VolumeCategory::Master.step(&mut profile, 3);
+        assert_near(profile.master_volume, 1.0);
+        // Only the stepped category moves
+        assert_near(profile.sfx_volume, 0.4);
+    }
+
+    fn cue_app() -> App {
+        let mut app = App::new();
+        app.add_message::<Jumped>()
+            .add_message::<Slid>()
+            .add_message::<LaneChanged>()
+            .add_message::<CoinCollected>()
+            .add_message::<Stumbled>()
+            .add_message::<PlayerDied>()
+            .add_message::<SoundCue>()
+            .add_systems(Update, (cue_gameplay_sounds, cue_power_ups).chain());
+        app
+    }
+
+    fn sent_cues(app: &mut App) -> Vec<SoundCue> {
+        app.world_mut()
+            .resource_mut::<Messages<SoundCue>>()
+            .drain()
+            .collect()
+    }
+
+    #[test]
+    fn gameplay_events_cue_their_sounds() {
+        let mut app = cue_app();
+        let player = app.world_mut().spawn(Player).id();
+        let cause = Cause::player(player);
+
+        let world = app.world_mut();
+        world.write_message(Jumped { cause });
+        world.write_message(Jumped { cause });
+        world.write_message(Slid { cause });
+        world.write_message(LaneChanged {
+            cause,
+            from: 1,
+            to: 0,
+        });
+        world.write_message(CoinCollected { cause });
+        world.write_message(Stumbled { cause });
+        // Two hits in one frame are a single crash
+        for _ in 0..2 {
+            world.write_message(PlayerDied {
+                cause,
+                death: DeathCause::TrainFront,
+            });
+        }
+        app.update();
+
+        assert_eq!(
+            sent_cues(&mut app),
+            [
+                SoundCue::Jump,
+                SoundCue::Jump,
+                SoundCue::Slide,
+                SoundCue::LaneSwitch,
+                SoundCue::Coin,
+                SoundCue::Stumble,
+                SoundCue::Crash,
+            ]
+        );
+
+        // Events are only cued in the frame they happen
+        app.update();
+        assert!(sent_cues(&mut app).is_empty());
+    }
+
+    #[test]
+    fn riding_a_hoverboard_cues_the_power_up_once() {
+        let mut app = cue_app();
+        app.world_mut().spawn((
+            Player,
+            Hoverboard {
+                timer: Timer::from_seconds(1.0, TimerMode::Once),
+                board: Entity::PLACEHOLDER,
+            },
+        ));
+
+        app.update();
+        assert_eq!(sent_cues(&mut app), [SoundCue::PowerUp]);
+        app.update();
+        assert!(sent_cues(&mut app).is_empty());
+    }
+}
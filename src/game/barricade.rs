@@ -1,14 +1,25 @@
+use crate::game::day_cycle::NightLight;
+use crate::game::path::OnPath;
 use crate::game::player::Player;
 use crate::game::train::{Train, TrainType};
+use crate::resources::draw_horizon::DrawHorizon;
 use crate::resources::game_config::GameConfig;
+use crate::resources::lane_layout::LaneLayout;
+use crate::resources::theme::{Theme, Themes};
+use crate::resources::track_path::{TrackPath, snap_to_grid};
 use bevy::prelude::*;
 
-#[derive(Component, Clone, Copy, PartialEq)]
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
 pub enum ObstacleType {
     JumpOver,   // Low obstacle - must jump
     SlideUnder, // High obstacle - must slide
+    LowTunnel,  // Long overhead obstacle - must stay sliding all the way through
 }
 
+pub const TUNNEL_LENGTH: f32 = 10.0;
+// Underside of overhead obstacles
+pub const OVERHEAD_BOTTOM: f32 = 1.2;
+
 #[derive(Component)]
 pub struct Barricade {
     pub track_index: u8,
@@ -20,22 +31,33 @@ pub struct CollisionShape {
     pub size: Vec3,
 }
 
+/// What the generator decided to place
+pub struct ObstacleSpec {
+    pub track_index: u8,
+    pub z_position: f32,
+    pub obstacle_type: ObstacleType,
+}
+
 pub fn spawn_obstacle(
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
-    track_index: u8,
-    z_position: f32,
-    obstacle_type: ObstacleType,
-    game_config: &GameConfig,
+    spec: ObstacleSpec,
+    lane_layout: &LaneLayout,
+    theme: &Theme,
 ) -> Entity {
-    let x_offset = (track_index as f32 - 1.0) * game_config.track_spacing;
+    let ObstacleSpec {
+        track_index,
+        z_position,
+        obstacle_type,
+    } = spec;
+    let x_offset = lane_layout.x_position(track_index);
 
     match obstacle_type {
         ObstacleType::JumpOver => {
             // Construction barrier with alternating red and cream upright blocks
-            let red_color = materials.add(Color::srgb(0.9, 0.2, 0.15)); // Bright red
-            let cream_color = materials.add(Color::srgb(0.95, 0.9, 0.75)); // Cream/tan
+            let red_color = materials.add(theme.barricade);
+            let cream_color = materials.add(theme.barricade_trim);
 
             // Upright block segments
             let block_mesh = meshes.add(Cuboid::new(0.18, 0.5, 0.12));
@@ -50,6 +72,7 @@ pub fn spawn_obstacle(
                     CollisionShape {
                         size: Vec3::new(1.5, 0.7, 0.3),
                     },
+                    OnPath,
                     Transform::from_translation(Vec3::new(x_offset, 0.0, z_position)),
                     Visibility::default(),
                 ))
@@ -90,9 +113,9 @@ pub fn spawn_obstacle(
         }
         ObstacleType::SlideUnder => {
             // Overhead barrier with alternating red/cream upright blocks
-            let red_color = materials.add(Color::srgb(0.9, 0.2, 0.15)); // Bright red
-            let cream_color = materials.add(Color::srgb(0.95, 0.9, 0.75)); // Cream/tan
-            let light_color = materials.add(Color::srgb(1.0, 0.85, 0.1)); // Yellow warning light
+            let red_color = materials.add(theme.barricade);
+            let cream_color = materials.add(theme.barricade_trim);
+            let light_color = materials.add(theme.warning_light);
 
             let block_mesh = meshes.add(Cuboid::new(0.18, 0.55, 0.1));
             let pole_mesh = meshes.add(Cuboid::new(0.15, 1.4, 0.15));
@@ -107,6 +130,7 @@ pub fn spawn_obstacle(
                     CollisionShape {
                         size: Vec3::new(1.5, 0.7, 0.3),
                     },
+                    OnPath,
                     Transform::from_translation(Vec3::new(x_offset, 0.0, z_position)),
                     Visibility::default(),
                 ))
@@ -142,16 +166,60 @@ pub fn spawn_obstacle(
                         MeshMaterial3d(cream_color.clone()),
                         Transform::from_xyz(0.65, 0.7, 0.0),
                     ));
-                    // Warning lights on top
+                    // Warning lights on top, lit after dark
+                    for x in [-0.5, 0.5] {
+                        parent.spawn((
+                            NightLight {
+                                emissive: theme.warning_light.to_linear() * 3.0,
+                            },
+                            Mesh3d(light_mesh.clone()),
+                            MeshMaterial3d(light_color.clone()),
+                            Transform::from_xyz(x, 1.85, 0.0),
+                        ));
+                    }
+                })
+                .id()
+        }
+        ObstacleType::LowTunnel => {
+            // Concrete tunnel mouth with a low roof running over the track
+            let concrete_color = materials.add(theme.tunnel);
+            let stripe_color = materials.add(theme.warning_light); // Hazard stripe
+
+            let wall_mesh = meshes.add(Cuboid::new(0.2, OVERHEAD_BOTTOM, TUNNEL_LENGTH));
+            let roof_mesh = meshes.add(Cuboid::new(1.8, 0.4, TUNNEL_LENGTH));
+            let stripe_mesh = meshes.add(Cuboid::new(1.82, 0.12, 0.05));
+
+            commands
+                .spawn((
+                    Barricade {
+                        track_index,
+                        obstacle_type,
+                    },
+                    CollisionShape {
+                        size: Vec3::new(1.5, 0.4, TUNNEL_LENGTH),
+                    },
+                    OnPath,
+                    Transform::from_translation(Vec3::new(x_offset, 0.0, z_position)),
+                    Visibility::default(),
+                ))
+                .with_children(|parent| {
+                    for side in [-1.0, 1.0] {
+                        parent.spawn((
+                            Mesh3d(wall_mesh.clone()),
+                            MeshMaterial3d(concrete_color.clone()),
+                            Transform::from_xyz(side * 0.8, OVERHEAD_BOTTOM / 2.0, 0.0),
+                        ));
+                    }
                     parent.spawn((
-                        Mesh3d(light_mesh.clone()),
-                        MeshMaterial3d(light_color.clone()),
-                        Transform::from_xyz(-0.5, 1.85, 0.0),
+                        Mesh3d(roof_mesh),
+                        MeshMaterial3d(concrete_color.clone()),
+                        Transform::from_xyz(0.0, OVERHEAD_BOTTOM + 0.2, 0.0),
                     ));
+                    // Hazard stripe across the entrance so the tunnel reads from a distance
                     parent.spawn((
-                        Mesh3d(light_mesh.clone()),
-                        MeshMaterial3d(light_color.clone()),
-                        Transform::from_xyz(0.5, 1.85, 0.0),
+                        Mesh3d(stripe_mesh),
+                        MeshMaterial3d(stripe_color),
+                        Transform::from_xyz(0.0, OVERHEAD_BOTTOM + 0.1, -TUNNEL_LENGTH / 2.0),
                     ));
                 })
                 .id()
@@ -168,11 +236,38 @@ fn pseudo_random(seed: u32) -> u32 {
     x
 }
 
+// Salt for barricade spawn keys
+const OBSTACLE_SALT: u32 = 0xDEAD_BEEF;
+
+/// Lane and kind of the barricade at grid spot `z`, picked from the track seed and the
+/// spot alone so a seed always lays out the same obstacles
+fn plan_obstacle(
+    z: f32,
+    track_path: &TrackPath,
+    lane_layout: &LaneLayout,
+    game_config: &GameConfig,
+) -> (u8, ObstacleType) {
+    let seed = pseudo_random(track_path.spawn_key(OBSTACLE_SALT, z));
+    let track_index = lane_layout.random_open_lane(seed, z);
+
+    // Mix obstacle types - more JumpOver obstacles (easier)
+    let obstacle_type = match (seed / 7) % 8 {
+        0 | 1 => ObstacleType::SlideUnder,
+        2 if game_config.difficulty_at(z) > 1.5 => ObstacleType::LowTunnel,
+        _ => ObstacleType::JumpOver,
+    };
+    (track_index, obstacle_type)
+}
+
 pub fn generate_obstacles_procedurally(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     game_config: Res<GameConfig>,
+    lane_layout: Res<LaneLayout>,
+    themes: Res<Themes>,
+    draw_horizon: Res<DrawHorizon>,
+    track_path: Res<TrackPath>,
     barricade_query: Query<&Transform, With<Barricade>>,
     player_query: Query<&Transform, (With<Player>, Without<Barricade>, Without<Train>)>,
     train_query: Query<(&Transform, &Train), Without<Barricade>>,
@@ -188,15 +283,13 @@ pub fn generate_obstacles_procedurally(
         // Minimum spawn distance ahead of player
         let min_spawn_distance = 50.0;
         // Maximum spawn distance ahead
-        let max_spawn_distance = 100.0;
+        let max_spawn_distance = draw_horizon.fill_distance(100.0);
         // Minimum gap between obstacles
         let min_obstacle_gap = 20.0;
 
         // Find the furthest barricade
         let mut max_barricade_z = player_z;
-        let mut obstacle_count: u32 = 0;
         for transform in barricade_query.iter() {
-            obstacle_count += 1;
             if transform.translation.z > max_barricade_z {
                 max_barricade_z = transform.translation.z;
             }
@@ -226,82 +319,41 @@ pub fn generate_obstacles_procedurally(
         // Only spawn if there's room and we need more obstacles ahead
         let spawn_threshold = player_z + min_spawn_distance;
 
-        if max_barricade_z < spawn_threshold {
+        let z_position = if max_barricade_z < spawn_threshold {
             // Spawn new obstacle well ahead of player
-            let target_z = player_z + min_spawn_distance + 15.0;
-
-            // Use obstacle count + player position as seed for better randomness
-            let seed = pseudo_random(
-                obstacle_count
-                    .wrapping_add((target_z * 3.7) as u32)
-                    .wrapping_add(0xDEADBEEF),
-            );
-
-            // Ensure variety in track selection
-            let track_index = (seed % 3) as u8;
-
-            // Skip if this would conflict with a ramped train
-            if conflicts_with_ramp(track_index, target_z) {
+            snap_to_grid(player_z + min_spawn_distance + 15.0)
+        } else if max_barricade_z < player_z + max_spawn_distance {
+            // Add more obstacles ahead but maintain spacing
+            let next_z = snap_to_grid(max_barricade_z + min_obstacle_gap);
+            if next_z >= player_z + max_spawn_distance {
                 return;
             }
+            next_z
+        } else {
+            return;
+        };
 
-            // Mix obstacle types - more JumpOver obstacles (easier)
-            let obstacle_type = if (seed / 7) % 4 == 0 {
-                ObstacleType::SlideUnder
-            } else {
-                ObstacleType::JumpOver
-            };
-
-            spawn_obstacle(
-                &mut commands,
-                &mut meshes,
-                &mut materials,
-                track_index,
-                target_z,
-                obstacle_type,
-                &game_config,
-            );
-        } else if max_barricade_z < player_z + max_spawn_distance {
-            // Add more obstacles ahead but maintain spacing
-            let next_z = max_barricade_z + min_obstacle_gap;
-
-            if next_z < player_z + max_spawn_distance {
-                // Different seed for additional obstacles
-                let seed = pseudo_random(
-                    obstacle_count
-                        .wrapping_mul(31)
-                        .wrapping_add((next_z * 5.3) as u32)
-                        .wrapping_add(0xCAFEBABE),
-                );
-                let track_index = (seed % 3) as u8;
-
-                // Skip if this would conflict with a ramped train
-                if conflicts_with_ramp(track_index, next_z) {
-                    return;
-                }
+        let (track_index, obstacle_type) =
+            plan_obstacle(z_position, &track_path, &lane_layout, &game_config);
 
-                let obstacle_type = if (seed / 5) % 3 == 0 {
-                    ObstacleType::SlideUnder
-                } else {
-                    ObstacleType::JumpOver
-                };
-
-                spawn_obstacle(
-                    &mut commands,
-                    &mut meshes,
-                    &mut materials,
-                    track_index,
-                    next_z,
-                    obstacle_type,
-                    &game_config,
-                );
-            }
+        // Skip if this would conflict with a ramped train
+        if conflicts_with_ramp(track_index, z_position) {
+            return;
         }
-    }
-}
 
-pub fn scale_difficulty(mut game_config: ResMut<GameConfig>, time: Res<Time>) {
-    game_config.difficulty_scale = 1.0 + (time.elapsed_secs() * 0.1).min(5.0);
+        spawn_obstacle(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            ObstacleSpec {
+                track_index,
+                z_position,
+                obstacle_type,
+            },
+            &lane_layout,
+            &themes.at(z_position),
+        );
+    }
 }
 
 pub fn recycle_barricades(
@@ -319,3 +371,45 @@ pub fn recycle_barricades(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lanes and kinds of forty barricades laid `min_obstacle_gap` apart, starting from
+    /// wherever the player happened to be when the first was placed
+    fn layout(seed: u32, first_z: f32) -> Vec<(u8, ObstacleType)> {
+        let track_path = TrackPath::new(seed);
+        let lane_layout = LaneLayout::default();
+        let game_config = GameConfig::default();
+        let mut z = snap_to_grid(first_z);
+        (0..40)
+            .map(|_| {
+                let planned = plan_obstacle(z, &track_path, &lane_layout, &game_config);
+                z = snap_to_grid(z + 20.0);
+                planned
+            })
+            .collect()
+    }
+
+    #[test]
+    fn one_seed_lays_out_one_run() {
+        assert_eq!(layout(7, 100.0), layout(7, 100.0));
+        // Frame timing within a grid spot doesn't move or change anything
+        assert_eq!(layout(7, 100.1), layout(7, 101.9));
+        assert_ne!(layout(7, 100.0), layout(8, 100.0));
+    }
+
+    #[test]
+    fn low_tunnels_wait_for_difficulty_to_rise() {
+        let game_config = GameConfig::default();
+        let lane_layout = LaneLayout::default();
+        let track_path = TrackPath::new(7);
+        let early_z = game_config.difficulty_distance * 0.5;
+        assert!((0..early_z as i32).step_by(2).all(|z| {
+            let (_, obstacle_type) =
+                plan_obstacle(z as f32, &track_path, &lane_layout, &game_config);
+            obstacle_type != ObstacleType::LowTunnel
+        }));
+    }
+}
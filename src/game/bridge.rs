@@ -0,0 +1,170 @@
+use crate::game::path::OnPath;
+use crate::game::player::Player;
+use crate::resources::draw_horizon::DrawHorizon;
+use crate::resources::game_config::GameConfig;
+use crate::resources::lane_layout::{LaneLayout, LaneSection};
+use crate::resources::track_path::{TrackPath, snap_to_grid};
+use bevy::prelude::*;
+
+/// Narrow bridge where only some of the lanes carry on
+#[derive(Component)]
+pub struct Bridge {
+    pub end_z: f32,
+}
+
+const BRIDGE_MIN_DIFFICULTY: f32 = 1.5;
+// Far enough ahead that no train, barricade or coin has been placed there yet; pushed
+// out further when the draw horizon reaches past it
+const BRIDGE_LEAD: f32 = 300.0;
+const BRIDGE_MIN_LENGTH: f32 = 40.0;
+const BRIDGE_MAX_EXTRA_LENGTH: u32 = 40;
+const BRIDGE_MAX_EXTRA_GAP: u32 = 120;
+// Salt for bridge spawn keys
+const BRIDGE_SALT: u32 = 0x0B81_D6E5;
+// Girders are built from short pieces so they follow bends in the track path
+const GIRDER_PIECE_LENGTH: f32 = 5.0;
+
+// Simple pseudo-random for bridge placement
+fn bridge_random(seed: u32) -> u32 {
+    let mut x = seed;
+    x ^= x << 13;
+    x ^= x >> 17;
+    x ^= x << 5;
+    x
+}
+
+/// Lays out the next bridge once the previous one is behind the player
+pub fn generate_bridges(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut lane_layout: ResMut<LaneLayout>,
+    game_config: Res<GameConfig>,
+    draw_horizon: Res<DrawHorizon>,
+    track_path: Res<TrackPath>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    let Ok(player_transform) = player_query.single() else {
+        return;
+    };
+    let player_z = player_transform.translation.z;
+
+    if game_config.difficulty_at(player_z) < BRIDGE_MIN_DIFFICULTY
+        || lane_layout
+            .sections()
+            .iter()
+            .any(|section| section.end_z > player_z)
+    {
+        return;
+    }
+
+    // Seeded from the track and the spot only, so the track seed picks the bridge
+    let lead = BRIDGE_LEAD.max(draw_horizon.gameplay_distance() + 20.0);
+    let earliest_z = snap_to_grid(player_z + lead);
+    let seed = bridge_random(track_path.spawn_key(BRIDGE_SALT, earliest_z));
+    let start_z = earliest_z + (seed % BRIDGE_MAX_EXTRA_GAP) as f32;
+    let length = BRIDGE_MIN_LENGTH + (seed / 7 % BRIDGE_MAX_EXTRA_LENGTH) as f32;
+
+    // Leave a run of at least one lane open, never all of them
+    let open_count = 1 + (seed / 13) as u8 % (lane_layout.count - 1);
+    let first_open = (seed / 17) as u8 % (lane_layout.count - open_count + 1);
+    let section = LaneSection {
+        start_z,
+        end_z: start_z + length,
+        first_open,
+        last_open: first_open + open_count - 1,
+    };
+
+    spawn_bridge(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &section,
+        &lane_layout,
+    );
+    lane_layout.add_section(section);
+}
+
+fn spawn_bridge(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    section: &LaneSection,
+    lane_layout: &LaneLayout,
+) -> Entity {
+    let length = section.end_z - section.start_z;
+    let center_z = (section.start_z + section.end_z) / 2.0;
+    let half_lane = lane_layout.spacing / 2.0;
+    let left_edge = lane_layout.x_position(section.first_open) - half_lane;
+    let right_edge = lane_layout.x_position(section.last_open) + half_lane;
+
+    let girder_pieces = (length / GIRDER_PIECE_LENGTH).ceil();
+    let piece_length = length / girder_pieces;
+    let girder_mesh = meshes.add(Cuboid::new(0.3, 1.4, piece_length));
+    let girder_material = materials.add(Color::srgb(0.3, 0.35, 0.4)); // Painted steel
+    let barrier_mesh = meshes.add(Cuboid::new(lane_layout.spacing * 0.8, 0.8, 0.2));
+    let barrier_material = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.9, 0.1, 0.1),
+        emissive: LinearRgba::rgb(1.5, 0.1, 0.1),
+        ..default()
+    });
+
+    commands
+        .spawn((
+            Bridge {
+                end_z: section.end_z,
+            },
+            OnPath,
+            Transform::from_xyz(0.0, 0.0, center_z),
+            Visibility::default(),
+        ))
+        .with_children(|parent| {
+            // Side girders along the edges of the open lanes
+            for x in [left_edge, right_edge] {
+                for i in 0..girder_pieces as i32 {
+                    let z = -length / 2.0 + (i as f32 + 0.5) * piece_length;
+                    parent.spawn((
+                        Mesh3d(girder_mesh.clone()),
+                        MeshMaterial3d(girder_material.clone()),
+                        Transform::from_xyz(x, 0.7, z),
+                    ));
+                }
+            }
+
+            // Red boards across the start of each closed lane
+            for lane in lane_layout.lanes() {
+                if (section.first_open..=section.last_open).contains(&lane) {
+                    continue;
+                }
+                parent.spawn((
+                    Mesh3d(barrier_mesh.clone()),
+                    MeshMaterial3d(barrier_material.clone()),
+                    Transform::from_xyz(lane_layout.x_position(lane), 0.6, -length / 2.0),
+                ));
+            }
+        })
+        .id()
+}
+
+pub fn recycle_bridges(
+    mut commands: Commands,
+    mut lane_layout: ResMut<LaneLayout>,
+    bridge_query: Query<(Entity, &Bridge)>,
+    camera_query: Query<&Transform, With<Camera3d>>,
+) {
+    if let Ok(camera_transform) = camera_query.single() {
+        let cutoff = camera_transform.translation.z - 20.0;
+        for (entity, bridge) in bridge_query.iter() {
+            if bridge.end_z < cutoff {
+                commands.entity(entity).despawn();
+            }
+        }
+        lane_layout.prune_sections(cutoff);
+    }
+}
+
+pub fn despawn_bridges(mut commands: Commands, bridge_query: Query<Entity, With<Bridge>>) {
+    for entity in bridge_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -1,9 +1,23 @@
+use crate::game::chaser::Chaser;
+use crate::game::path::OnPath;
 use crate::game::player::Player;
+use crate::resources::draw_horizon::DrawHorizon;
 use bevy::prelude::*;
 
-pub fn setup_camera(mut commands: Commands) {
+pub fn setup_camera(mut commands: Commands, draw_horizon: Res<DrawHorizon>) {
     commands.spawn((
         Camera3d::default(),
+        OnPath,
+        // Thickens to hide everything just short of the draw horizon; its colour follows the sky
+        DistanceFog {
+            falloff: FogFalloff::Linear {
+                start: draw_horizon.fog_start(),
+                end: draw_horizon.fog_end(),
+            },
+            ..default()
+        },
+        // Passing trains are heard from here
+        SpatialListener::default(),
         Transform::from_xyz(0.0, 5.0, -10.0).looking_at(Vec3::new(0.0, 0.0, 10.0), Vec3::Y),
     ));
 
@@ -28,12 +42,21 @@ pub fn setup_camera(mut commands: Commands) {
 pub fn follow_player(
     mut camera_query: Query<&mut Transform, (With<Camera3d>, Without<Player>)>,
     player_query: Query<&Transform, (With<Player>, Without<Camera3d>)>,
+    chaser_query: Query<(), With<Chaser>>,
+    mut pull_back: Local<f32>,
+    time: Res<Time>,
 ) {
     if let Ok(player_transform) = player_query.single() {
         if let Ok(mut camera_transform) = camera_query.single_mut() {
             let player_z = player_transform.translation.z;
+
+            // Ease further back and up while a chaser is on screen so both stay in frame
+            let target_pull_back = if chaser_query.is_empty() { 0.0 } else { 1.0 };
+            *pull_back += (target_pull_back - *pull_back) * (3.0 * time.delta_secs()).min(1.0);
+
             // Keep camera behind player
-            camera_transform.translation.z = player_z - 10.0;
+            camera_transform.translation.z = player_z - 10.0 - 3.0 * *pull_back;
+            camera_transform.translation.y = 5.0 + 1.5 * *pull_back;
             // Look at a point ahead of the player
             let look_target = Vec3::new(0.0, 0.5, player_z + 5.0);
             camera_transform.look_at(look_target, Vec3::Y);
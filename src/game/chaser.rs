@@ -0,0 +1,271 @@
+use crate::game::path::OnPath;
+use crate::game::player::{CurrentTrack, LaneSwitch, LastLaneChange, Player};
+use crate::resources::game_config::GameConfig;
+use crate::resources::lane_layout::LaneLayout;
+use bevy::prelude::*;
+
+/// Guard that runs behind the player after a stumble
+#[derive(Component)]
+pub struct Chaser {
+    pub gap: f32, // Distance behind the player
+}
+
+/// Present on the player while a stumble is being punished
+#[derive(Component)]
+pub struct Stumble {
+    pub window: Timer,            // A second stumble inside this window ends the run
+    pub recovery: Timer,          // Time to get back to full speed
+    pub obstacle: Option<Entity>, // What tripped the player, until they've bounced clear of it
+}
+
+impl Stumble {
+    pub fn new(game_config: &GameConfig, obstacle: Entity) -> Self {
+        Self {
+            window: Timer::from_seconds(game_config.stumble_window, TimerMode::Once),
+            recovery: Timer::from_seconds(game_config.stumble_recovery, TimerMode::Once),
+            obstacle: Some(obstacle),
+        }
+    }
+
+    /// Whether a hit on `entity` is the player still overlapping what tripped them
+    pub fn ignores(&self, entity: Entity) -> bool {
+        self.obstacle == Some(entity)
+    }
+
+    /// Multiplier on forward speed, easing from the slowdown back to 1.0
+    pub fn speed_factor(&self, game_config: &GameConfig) -> f32 {
+        let progress = self.recovery.fraction();
+        game_config.stumble_slowdown + (1.0 - game_config.stumble_slowdown) * progress
+    }
+}
+
+// Gap at which a chaser that gave up is removed
+const CHASER_GIVE_UP_GAP: f32 = 15.0;
+const CHASER_FALL_BACK_SPEED: f32 = 4.0;
+
+/// Lane to bounce back to after clipping an obstacle at `obstacle_x`: whichever end of the
+/// lane change lies away from it, so a player reversing out of a lane isn't sent back into it
+pub fn bounce_track(
+    current_track: CurrentTrack,
+    lane_switch: Option<&LaneSwitch>,
+    lane_change: &LastLaneChange,
+    obstacle_x: f32,
+    lane_layout: &LaneLayout,
+) -> CurrentTrack {
+    let Some(lane_switch) = lane_switch else {
+        return lane_change.from;
+    };
+    let clearance = |track: CurrentTrack| (track.x_position(lane_layout) - obstacle_x).abs();
+    if clearance(current_track) > clearance(lane_switch.from) {
+        current_track
+    } else {
+        lane_switch.from
+    }
+}
+
+/// Slows the player and bounces them to `bounce_track`; the guard is set on them by
+/// `spawn_chaser_on_stumble`
+pub fn stumble(
+    player_x: f32,
+    current_track: CurrentTrack,
+    bounce_track: CurrentTrack,
+    obstacle: Entity,
+    lane_layout: &LaneLayout,
+    game_config: &GameConfig,
+) -> impl Bundle {
+    (
+        Stumble::new(game_config, obstacle),
+        LaneSwitch::new(
+            current_track,
+            player_x,
+            bounce_track.x_position(lane_layout),
+            lane_layout,
+            game_config,
+        ),
+        LastLaneChange {
+            from: current_track,
+        },
+        bounce_track,
+    )
+}
+
+pub fn spawn_chaser_on_stumble(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    player_query: Query<&Transform, Added<Stumble>>,
+    chaser_query: Query<Entity, With<Chaser>>,
+    game_config: Res<GameConfig>,
+) {
+    let Ok(player_transform) = player_query.single() else {
+        return;
+    };
+    if !chaser_query.is_empty() {
+        return;
+    }
+
+    spawn_chaser(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        player_transform.translation,
+        &game_config,
+    );
+}
+
+pub fn spawn_chaser(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    player_position: Vec3,
+    game_config: &GameConfig,
+) -> Entity {
+    let uniform_color = materials.add(Color::srgb(0.15, 0.2, 0.45)); // Navy uniform
+    let skin_color = materials.add(Color::srgb(0.85, 0.7, 0.55));
+    let cap_color = materials.add(Color::srgb(0.1, 0.1, 0.15));
+
+    let body_mesh = meshes.add(Capsule3d::new(0.3, 0.9));
+    let head_mesh = meshes.add(Sphere::new(0.2));
+    let cap_mesh = meshes.add(Cylinder::new(0.22, 0.1));
+
+    commands
+        .spawn((
+            Chaser {
+                gap: game_config.chaser_gap,
+            },
+            OnPath,
+            Transform::from_xyz(
+                player_position.x,
+                player_position.y,
+                player_position.z - game_config.chaser_gap,
+            ),
+            Visibility::default(),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Mesh3d(body_mesh),
+                MeshMaterial3d(uniform_color),
+                Transform::from_xyz(0.0, 0.0, 0.0),
+            ));
+            parent.spawn((
+                Mesh3d(head_mesh),
+                MeshMaterial3d(skin_color),
+                Transform::from_xyz(0.0, 0.8, 0.0),
+            ));
+            parent.spawn((
+                Mesh3d(cap_mesh),
+                MeshMaterial3d(cap_color),
+                Transform::from_xyz(0.0, 0.98, 0.0),
+            ));
+        })
+        .id()
+}
+
+pub fn tick_stumble(
+    mut commands: Commands,
+    mut player_query: Query<(Entity, &mut Stumble, Has<LaneSwitch>), With<Player>>,
+    time: Res<Time>,
+) {
+    for (entity, mut stumble, switching) in player_query.iter_mut() {
+        stumble.window.tick(time.delta());
+        stumble.recovery.tick(time.delta());
+        // Once the bounce has carried the player clear, running back into the obstacle
+        // is a fresh hit
+        if !switching {
+            stumble.obstacle = None;
+        }
+        if stumble.window.just_finished() {
+            commands.entity(entity).remove::<Stumble>();
+        }
+    }
+}
+
+pub fn follow_player_chaser(
+    mut commands: Commands,
+    mut chaser_query: Query<(Entity, &mut Chaser, &mut Transform), Without<Player>>,
+    player_query: Query<(&Transform, Has<Stumble>), With<Player>>,
+    game_config: Res<GameConfig>,
+    time: Res<Time>,
+) {
+    let Ok((player_transform, stumbling)) = player_query.single() else {
+        return;
+    };
+    let player_pos = player_transform.translation;
+
+    for (entity, mut chaser, mut transform) in chaser_query.iter_mut() {
+        // Once the stumble window passes the guard gives up and drops back
+        if !stumbling {
+            chaser.gap += CHASER_FALL_BACK_SPEED * time.delta_secs();
+            if chaser.gap > CHASER_GIVE_UP_GAP {
+                commands.entity(entity).despawn();
+                continue;
+            }
+        } else {
+            chaser.gap = game_config.chaser_gap;
+        }
+
+        let lerp_factor = (8.0 * time.delta_secs()).min(1.0);
+        transform.translation.x += (player_pos.x - transform.translation.x) * lerp_factor;
+        transform.translation.y = player_pos.y.min(1.5);
+        transform.translation.z = player_pos.z - chaser.gap;
+    }
+}
+
+pub fn despawn_chasers(
+    mut commands: Commands,
+    chaser_query: Query<Entity, With<Chaser>>,
+    player_query: Query<Entity, With<Player>>,
+) {
+    for entity in chaser_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    for entity in player_query.iter() {
+        commands.entity(entity).remove::<Stumble>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    fn stumble_app() -> App {
+        let mut app = App::new();
+        app.insert_resource(Time::<()>::default());
+        app
+    }
+
+    fn tick(app: &mut App) {
+        app.world_mut()
+            .run_system_once(tick_stumble)
+            .expect("tick_stumble only needs Time");
+    }
+
+    #[test]
+    fn running_back_into_the_obstacle_after_the_bounce_is_a_new_hit() {
+        let game_config = GameConfig::default();
+        let lane_layout = LaneLayout::default();
+        let mut app = stumble_app();
+        let obstacle = app.world_mut().spawn_empty().id();
+        let player = app
+            .world_mut()
+            .spawn((
+                Player,
+                Stumble::new(&game_config, obstacle),
+                LaneSwitch::new(CurrentTrack(1), 0.0, 1.0, &lane_layout, &game_config),
+            ))
+            .id();
+
+        // Still overlapping it while bouncing away
+        tick(&mut app);
+        let stumble = app.world().get::<Stumble>(player).unwrap();
+        assert!(stumble.ignores(obstacle));
+
+        // The bounce finishes, well inside the stumble window
+        app.world_mut().entity_mut(player).remove::<LaneSwitch>();
+        tick(&mut app);
+        let stumble = app.world().get::<Stumble>(player).unwrap();
+        assert!(!stumble.window.is_finished());
+        assert!(!stumble.ignores(obstacle));
+    }
+}
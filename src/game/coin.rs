@@ -1,14 +1,21 @@
 use crate::game::barricade::Barricade;
+use crate::game::events::{Cause, CauseKind, CoinCollected};
+use crate::game::path::OnPath;
 use crate::game::player::Player;
 use crate::game::train::Train;
-use crate::resources::game_config::GameConfig;
+use crate::resources::draw_horizon::DrawHorizon;
+use crate::resources::lane_layout::LaneLayout;
 use crate::resources::score::Score;
+use crate::resources::track_path::{TrackPath, snap_to_grid};
 use avian3d::prelude::*;
 use bevy::prelude::*;
 
 #[derive(Component)]
 pub struct Coin;
 
+// Salt for coin spawn keys
+const COIN_SALT: u32 = 0x0C01_CA7E;
+
 // Simple pseudo-random for coin spawning
 fn coin_random(seed: u32) -> u32 {
     let mut x = seed;
@@ -25,9 +32,9 @@ pub fn spawn_coin(
     track_index: u8,
     z_position: f32,
     y_offset: f32,
-    game_config: &GameConfig,
+    lane_layout: &LaneLayout,
 ) -> Entity {
-    let x_offset = (track_index as f32 - 1.0) * game_config.track_spacing;
+    let x_offset = lane_layout.x_position(track_index);
 
     // Golden coin appearance
     let coin_color = materials.add(StandardMaterial {
@@ -41,6 +48,7 @@ pub fn spawn_coin(
     commands
         .spawn((
             Coin,
+            OnPath,
             Mesh3d(coin_mesh),
             MeshMaterial3d(coin_color),
             Transform::from_xyz(x_offset, 0.8 + y_offset, z_position)
@@ -57,7 +65,9 @@ pub fn generate_coins_procedurally(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    game_config: Res<GameConfig>,
+    lane_layout: Res<LaneLayout>,
+    draw_horizon: Res<DrawHorizon>,
+    track_path: Res<TrackPath>,
     coin_query: Query<&Transform, With<Coin>>,
     player_query: Query<&Transform, (With<Player>, Without<Coin>)>,
     train_query: Query<(&Transform, &Train), (Without<Coin>, Without<Player>)>,
@@ -79,7 +89,7 @@ pub fn generate_coins_procedurally(
 
     // Spawn parameters
     let min_spawn_distance = 40.0;
-    let max_spawn_distance = 120.0;
+    let max_spawn_distance = draw_horizon.fill_distance(120.0);
     let min_coin_gap = 8.0;
 
     // Find the furthest coin
@@ -94,7 +104,10 @@ pub fn generate_coins_procedurally(
 
     // Helper to check if position conflicts with obstacles
     let position_is_clear = |track_idx: u8, z_pos: f32| -> bool {
-        let _track_x = (track_idx as f32 - 1.0) * game_config.track_spacing;
+        // Closed lanes on a bridge have nowhere to put coins
+        if !lane_layout.is_open(track_idx, z_pos) {
+            return false;
+        }
 
         // Check trains
         for (train_transform, train) in train_query.iter() {
@@ -124,18 +137,14 @@ pub fn generate_coins_procedurally(
     let spawn_threshold = player_z + min_spawn_distance;
 
     if max_coin_z < spawn_threshold || coin_count < 10 {
-        let base_z = if max_coin_z > player_z {
+        let base_z = snap_to_grid(if max_coin_z > player_z {
             max_coin_z + min_coin_gap
         } else {
             player_z + min_spawn_distance
-        };
+        });
 
-        // Generate seed for randomness
-        let seed = coin_random(
-            coin_count
-                .wrapping_add((base_z * 7.3) as u32)
-                .wrapping_add(0xC01CA7E),
-        );
+        // Seeded from the track and the spot only, so the track seed picks the pattern
+        let seed = coin_random(track_path.spawn_key(COIN_SALT, base_z));
 
         // Decide pattern type
         let pattern = seed % 4;
@@ -143,7 +152,7 @@ pub fn generate_coins_procedurally(
         match pattern {
             0 => {
                 // Single line of coins on one track
-                let track_index = ((seed / 4) % 3) as u8;
+                let track_index = lane_layout.random_open_lane(seed / 4, base_z);
                 for i in 0..5 {
                     let z_pos = base_z + (i as f32 * 2.0);
                     if z_pos < player_z + max_spawn_distance
@@ -156,17 +165,19 @@ pub fn generate_coins_procedurally(
                             track_index,
                             z_pos,
                             0.0,
-                            &game_config,
+                            &lane_layout,
                         );
                     }
                 }
             }
             1 => {
                 // Diagonal line across tracks
-                let start_track = ((seed / 4) % 3) as i8;
+                let lane_count = lane_layout.count as i8;
+                let start_track = lane_layout.random_open_lane(seed / 4, base_z) as i8;
                 let direction: i8 = if (seed / 12) % 2 == 0 { 1 } else { -1 };
                 for i in 0..5 {
-                    let track = (start_track + direction * (i as i8 % 3)).rem_euclid(3) as u8;
+                    let track = (start_track + direction * (i as i8 % lane_count))
+                        .rem_euclid(lane_count) as u8;
                     let z_pos = base_z + (i as f32 * 2.5);
                     if z_pos < player_z + max_spawn_distance && position_is_clear(track, z_pos) {
                         spawn_coin(
@@ -176,14 +187,14 @@ pub fn generate_coins_procedurally(
                             track,
                             z_pos,
                             0.0,
-                            &game_config,
+                            &lane_layout,
                         );
                     }
                 }
             }
             2 => {
                 // Arc pattern (coins going up then down)
-                let track_index = ((seed / 4) % 3) as u8;
+                let track_index = lane_layout.random_open_lane(seed / 4, base_z);
                 let heights = [0.0, 0.5, 1.0, 0.5, 0.0];
                 for (i, &height) in heights.iter().enumerate() {
                     let z_pos = base_z + (i as f32 * 2.0);
@@ -197,14 +208,14 @@ pub fn generate_coins_procedurally(
                             track_index,
                             z_pos,
                             height,
-                            &game_config,
+                            &lane_layout,
                         );
                     }
                 }
             }
             _ => {
-                // Cluster on all three tracks
-                for track in 0..3u8 {
+                // Cluster across every track
+                for track in lane_layout.lanes() {
                     let z_pos = base_z + (track as f32 * 0.5);
                     if z_pos < player_z + max_spawn_distance && position_is_clear(track, z_pos) {
                         spawn_coin(
@@ -214,7 +225,7 @@ pub fn generate_coins_procedurally(
                             track,
                             z_pos,
                             0.0,
-                            &game_config,
+                            &lane_layout,
                         );
                     }
                 }
@@ -225,7 +236,7 @@ pub fn generate_coins_procedurally(
 
 pub fn collect_coins(
     mut commands: Commands,
-    mut score: ResMut<Score>,
+    mut collected: MessageWriter<CoinCollected>,
     player_query: Query<&Transform, With<Player>>,
     coin_query: Query<(Entity, &Transform), With<Coin>>,
 ) {
@@ -245,12 +256,27 @@ pub fn collect_coins(
 
         if dx < 1.0 && dy < 1.5 && dz < 1.0 {
             // Collect the coin
-            score.coins += 1;
+            collected.write(CoinCollected {
+                cause: Cause {
+                    entity: coin_entity,
+                    kind: CauseKind::Coin,
+                },
+            });
             commands.entity(coin_entity).despawn();
         }
     }
 }
 
+pub fn count_collected_coins(
+    mut collected: MessageReader<CoinCollected>,
+    mut score: ResMut<Score>,
+) {
+    let count = collected.read().count() as u32;
+    if count > 0 {
+        score.coins += count;
+    }
+}
+
 pub fn recycle_coins(
     mut commands: Commands,
     coin_query: Query<(Entity, &Transform), With<Coin>>,
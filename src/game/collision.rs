@@ -0,0 +1,123 @@
+use crate::game::barricade::ObstacleType;
+use crate::game::player::PLAYER_HALF_WIDTH;
+use crate::game::train::TRAIN_WIDTH;
+use bevy::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CollisionKind {
+    Frontal,  // Ran straight into the obstacle
+    Glancing, // Moved into its side, or only clipped its edge
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HitOutcome {
+    Absorbed, // Hoverboard took the hit
+    Stumbled, // Player slows down and the chaser closes in
+    Died,
+}
+
+/// What ended the last run, for the game over screen
+#[derive(Resource, Clone, Copy, PartialEq, Debug, Default)]
+pub enum DeathCause {
+    #[default]
+    Unknown,
+    TrainFront, // Ran into the leading face of a train
+    TrainSide,  // Ran into a train somewhere along its body
+    JumpOver,   // Tripped over a barricade that had to be jumped
+    SlideUnder, // Hit a barricade that had to be slid under
+    LowTunnel,
+    Chaser, // Clipped something while stumbling, and the guard caught up
+}
+
+impl DeathCause {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            DeathCause::Unknown => "Crashed",
+            DeathCause::TrainFront => "Hit head-on by a train",
+            DeathCause::TrainSide => "Ran into the side of a train",
+            DeathCause::JumpOver => "Tripped over a barrier",
+            DeathCause::SlideUnder => "Hit a barrier instead of sliding under it",
+            DeathCause::LowTunnel => "Hit a low tunnel",
+            DeathCause::Chaser => "Caught by the guard",
+        }
+    }
+
+    /// Cause of a lethal hit on a barricade
+    pub fn from_barricade(
+        obstacle_type: ObstacleType,
+        kind: CollisionKind,
+        stumbling: bool,
+    ) -> Self {
+        if stumbling && kind == CollisionKind::Glancing {
+            return DeathCause::Chaser;
+        }
+        match obstacle_type {
+            ObstacleType::JumpOver => DeathCause::JumpOver,
+            ObstacleType::SlideUnder => DeathCause::SlideUnder,
+            ObstacleType::LowTunnel => DeathCause::LowTunnel,
+        }
+    }
+
+    /// Cause of a lethal hit on a train; running into its body counts as its side
+    pub fn from_train(
+        player_z: f32,
+        train_back: f32,
+        kind: CollisionKind,
+        stumbling: bool,
+    ) -> Self {
+        if beside_train(player_z, train_back) {
+            DeathCause::TrainSide
+        } else if stumbling && kind == CollisionKind::Glancing {
+            DeathCause::Chaser
+        } else {
+            DeathCause::TrainFront
+        }
+    }
+}
+
+/// Whether the player is alongside a train's body rather than in front of it
+fn beside_train(player_z: f32, train_back: f32) -> bool {
+    player_z > train_back + 0.5
+}
+
+/// How far the player can overlap an obstacle's edge and still only clip it
+const EDGE_CLIP_DEPTH: f32 = 0.25;
+
+/// Whether the player only overlaps the obstacle's edge rather than being square on to it
+fn clips_edge(lateral_offset: f32, obstacle_half_width: f32) -> bool {
+    let overlap = obstacle_half_width + PLAYER_HALF_WIDTH - lateral_offset;
+    overlap < EDGE_CLIP_DEPTH
+}
+
+/// A barricade is frontal unless the player only clipped its edge, whether or not
+/// they were changing lanes at the time
+pub fn classify_barricade_hit(lateral_offset: f32, obstacle_half_width: f32) -> CollisionKind {
+    if clips_edge(lateral_offset, obstacle_half_width) {
+        CollisionKind::Glancing
+    } else {
+        CollisionKind::Frontal
+    }
+}
+
+/// A train is frontal when met at its leading face; anywhere along the body
+/// means the player moved into its side. Running into the leading face mid lane
+/// switch is still frontal unless the player only clipped its corner
+pub fn classify_train_hit(player_z: f32, train_back: f32, lateral_offset: f32) -> CollisionKind {
+    if beside_train(player_z, train_back) || clips_edge(lateral_offset, TRAIN_WIDTH / 2.0) {
+        CollisionKind::Glancing
+    } else {
+        CollisionKind::Frontal
+    }
+}
+
+/// Frontal hits are lethal, and so is a second bump while the chaser is still on you
+pub fn resolve_hit(kind: CollisionKind, has_hoverboard: bool, stumbling: bool) -> HitOutcome {
+    let lethal = kind == CollisionKind::Frontal || stumbling;
+    if !lethal {
+        HitOutcome::Stumbled
+    } else if has_hoverboard {
+        HitOutcome::Absorbed
+    } else {
+        HitOutcome::Died
+    }
+}
@@ -0,0 +1,20 @@
+use crate::resources::daily::{DailyModifier, DailyRun};
+use crate::resources::profile::PlayerProfile;
+use crate::resources::score::Score;
+use bevy::prelude::*;
+
+/// Run condition keeping hoverboards out of daily runs without power-ups
+pub fn power_ups_allowed(daily_run: Res<DailyRun>) -> bool {
+    !daily_run.has(DailyModifier::NoPowerUps)
+}
+
+/// Keeps the day's best daily run score in the profile
+pub fn record_daily_score(
+    daily_run: Res<DailyRun>,
+    score: Res<Score>,
+    mut profile: ResMut<PlayerProfile>,
+) {
+    if daily_run.active && profile.record_daily_score(daily_run.date, score.points(1)) {
+        profile.save();
+    }
+}
@@ -0,0 +1,44 @@
+use crate::resources::day_cycle::DayCycle;
+use crate::resources::game_config::GameConfig;
+use crate::resources::track_path::TrackPath;
+use bevy::prelude::*;
+
+/// Mesh whose material glows after dark, like a lit window or a warning lamp
+#[derive(Component, Clone, Copy)]
+pub struct NightLight {
+    pub emissive: LinearRgba, // Glow at full night
+}
+
+pub fn advance_day_cycle(
+    mut day_cycle: ResMut<DayCycle>,
+    camera_query: Query<&Transform, With<Camera3d>>,
+    track_path: Res<TrackPath>,
+    game_config: Res<GameConfig>,
+) {
+    if let Ok(camera_transform) = camera_query.single() {
+        *day_cycle = DayCycle::at(
+            camera_transform.translation.z,
+            track_path.seed(),
+            &game_config,
+        );
+    }
+}
+
+/// Fades night lights in as the light goes
+pub fn light_night_lamps(
+    day_cycle: Res<DayCycle>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    lamp_query: Query<(&NightLight, &MeshMaterial3d<StandardMaterial>)>,
+) {
+    let night = 1.0 - day_cycle.daylight();
+    for (lamp, material) in lamp_query.iter() {
+        let target = lamp.emissive * night;
+        // Leave settled materials alone so they aren't uploaded again every frame
+        let settled = materials
+            .get(&material.0)
+            .is_none_or(|current| (current.emissive.to_vec3() - target.to_vec3()).length() < 0.01);
+        if !settled && let Some(current) = materials.get_mut(&material.0) {
+            current.emissive = target;
+        }
+    }
+}
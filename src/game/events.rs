@@ -0,0 +1,98 @@
+use crate::game::barricade::{Barricade, ObstacleType};
+use crate::game::collision::DeathCause;
+use crate::game::train::{Train, TrainType};
+use bevy::prelude::*;
+
+/// What kind of thing set off a gameplay event
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CauseKind {
+    Player,     // The player's own input
+    ClosedLane, // Steered out of a lane that closes ahead
+    Coin,
+    Barricade(ObstacleType),
+    Train(TrainType),
+}
+
+/// Entity behind a gameplay event, and what kind of thing it is
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Cause {
+    pub entity: Entity,
+    pub kind: CauseKind,
+}
+
+impl Cause {
+    pub fn player(entity: Entity) -> Self {
+        Self {
+            entity,
+            kind: CauseKind::Player,
+        }
+    }
+
+    pub fn barricade(entity: Entity, barricade: &Barricade) -> Self {
+        Self {
+            entity,
+            kind: CauseKind::Barricade(barricade.obstacle_type),
+        }
+    }
+
+    pub fn train(entity: Entity, train: &Train) -> Self {
+        Self {
+            entity,
+            kind: CauseKind::Train(train.train_type),
+        }
+    }
+}
+
+// Gameplay events, sent by the core systems as things happen in a run. Score, audio
+// and anything else that reacts to the run reads these rather than hooking into the
+// systems that send them.
+
+#[derive(Message, Clone, Copy, Debug)]
+pub struct CoinCollected {
+    pub cause: Cause,
+}
+
+/// The player got past an obstacle in their lane by jumping, sliding or riding over it
+#[derive(Message, Clone, Copy, Debug)]
+pub struct ObstacleCleared {
+    pub cause: Cause,
+}
+
+/// An obstacle went past just clear of the player
+#[derive(Message, Clone, Copy, Debug)]
+pub struct NearMiss {
+    pub cause: Cause,
+}
+
+#[derive(Message, Clone, Copy, Debug)]
+pub struct LaneChanged {
+    pub cause: Cause,
+    pub from: u8,
+    pub to: u8,
+}
+
+#[derive(Message, Clone, Copy, Debug)]
+pub struct Jumped {
+    pub cause: Cause,
+}
+
+#[derive(Message, Clone, Copy, Debug)]
+pub struct Slid {
+    pub cause: Cause,
+}
+
+#[derive(Message, Clone, Copy, Debug)]
+pub struct Landed {
+    pub cause: Cause,
+}
+
+#[derive(Message, Clone, Copy, Debug)]
+pub struct Stumbled {
+    pub cause: Cause,
+}
+
+#[derive(Message, Clone, Copy, Debug)]
+pub struct PlayerDied {
+    pub cause: Cause,
+    pub death: DeathCause,
+}
@@ -0,0 +1,331 @@
+use crate::game::action_map::{Action, ActionState};
+use crate::resources::game_config::GameConfig;
+use bevy::input::touch::{TouchInput, TouchPhase};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+// Touch id given to mouse drags so they share the touch code path
+const MOUSE_TOUCH_ID: u64 = u64::MAX;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Gesture {
+    SwipeLeft,
+    SwipeRight,
+    SwipeUp,
+    SwipeDown,
+    Tap,
+    DoubleTap,
+}
+
+impl Gesture {
+    fn action(self) -> Action {
+        match self {
+            Gesture::SwipeLeft => Action::MoveLeft,
+            Gesture::SwipeRight => Action::MoveRight,
+            Gesture::SwipeUp => Action::Jump,
+            Gesture::SwipeDown => Action::Slide,
+            Gesture::Tap => Action::Restart, // Only does anything on the game over screen
+            Gesture::DoubleTap => Action::Hoverboard,
+        }
+    }
+}
+
+/// One finger (or the mouse) from touching down to lifting off
+struct Stroke {
+    id: u64,
+    start: Vec2,
+    start_time: f32,
+    swipe: Option<Gesture>, // Set once the stroke is recognized, so it fires only once
+}
+
+/// Turns raw touch and drag samples into gestures
+#[derive(Resource, Default)]
+pub struct SwipeRecognizer {
+    stroke: Option<Stroke>, // Only the first finger down is tracked
+    last_tap: Option<f32>,  // Elapsed seconds of the previous tap, for double-taps
+}
+
+impl SwipeRecognizer {
+    /// Feeds one touch message taken at `now` seconds, returning a gesture once one is recognized
+    pub fn feed(
+        &mut self,
+        touch: &TouchInput,
+        now: f32,
+        game_config: &GameConfig,
+    ) -> Option<Gesture> {
+        self.feed_point(touch.id, touch.phase, touch.position, now, game_config)
+    }
+
+    fn feed_point(
+        &mut self,
+        id: u64,
+        phase: TouchPhase,
+        position: Vec2,
+        now: f32,
+        game_config: &GameConfig,
+    ) -> Option<Gesture> {
+        match phase {
+            TouchPhase::Started => {
+                if self.stroke.is_none() {
+                    self.stroke = Some(Stroke {
+                        id,
+                        start: position,
+                        start_time: now,
+                        swipe: None,
+                    });
+                }
+                None
+            }
+            TouchPhase::Moved => {
+                // Swipes fire mid-drag rather than on release so they feel immediate
+                let stroke = self.stroke.as_mut().filter(|stroke| stroke.id == id)?;
+                if stroke.swipe.is_some() {
+                    return None;
+                }
+                stroke.swipe = classify_swipe(
+                    position - stroke.start,
+                    now - stroke.start_time,
+                    game_config,
+                );
+                stroke.swipe
+            }
+            TouchPhase::Ended => {
+                let stroke = self.stroke.take_if(|stroke| stroke.id == id)?;
+                if stroke.swipe.is_some() {
+                    return None;
+                }
+
+                // A quick flick can end before any move sample crossed the threshold
+                let delta = position - stroke.start;
+                if let Some(swipe) = classify_swipe(delta, now - stroke.start_time, game_config) {
+                    return Some(swipe);
+                }
+                if delta.length() > game_config.tap_max_distance {
+                    return None;
+                }
+
+                let double_tapped = self
+                    .last_tap
+                    .is_some_and(|previous| now - previous <= game_config.double_tap_window);
+                if double_tapped {
+                    self.last_tap = None;
+                    Some(Gesture::DoubleTap)
+                } else {
+                    self.last_tap = Some(now);
+                    Some(Gesture::Tap)
+                }
+            }
+            TouchPhase::Canceled => {
+                if self.stroke.as_ref().is_some_and(|stroke| stroke.id == id) {
+                    self.stroke = None;
+                }
+                None
+            }
+        }
+    }
+
+    /// The swipe made by the finger that is still down, if it made one
+    pub fn held_swipe(&self) -> Option<Gesture> {
+        self.stroke.as_ref().and_then(|stroke| stroke.swipe)
+    }
+}
+
+fn classify_swipe(delta: Vec2, elapsed: f32, game_config: &GameConfig) -> Option<Gesture> {
+    let distance = delta.length();
+    if distance < game_config.swipe_min_distance
+        || distance / elapsed.max(f32::EPSILON) < game_config.swipe_min_velocity
+    {
+        return None;
+    }
+
+    // Screen y grows downwards
+    let gesture = if delta.x.abs() > delta.y.abs() {
+        if delta.x < 0.0 {
+            Gesture::SwipeLeft
+        } else {
+            Gesture::SwipeRight
+        }
+    } else if delta.y < 0.0 {
+        Gesture::SwipeUp
+    } else {
+        Gesture::SwipeDown
+    };
+    Some(gesture)
+}
+
+/// Presses the actions for this frame's gestures on top of the bound keys and buttons
+pub fn recognize_gestures(
+    mut touch_events: MessageReader<TouchInput>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut recognizer: ResMut<SwipeRecognizer>,
+    mut action_state: ResMut<ActionState>,
+    game_config: Res<GameConfig>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs();
+    let mut gestures = Vec::new();
+
+    for touch in touch_events.read() {
+        gestures.extend(recognizer.feed(touch, now, &game_config));
+    }
+
+    let mouse_phase = if mouse_buttons.just_pressed(MouseButton::Left) {
+        Some(TouchPhase::Started)
+    } else if mouse_buttons.just_released(MouseButton::Left) {
+        Some(TouchPhase::Ended)
+    } else if mouse_buttons.pressed(MouseButton::Left) {
+        Some(TouchPhase::Moved)
+    } else {
+        None
+    };
+    let cursor = windows.single().ok().and_then(Window::cursor_position);
+    match (mouse_phase, cursor) {
+        (Some(phase), Some(cursor)) => {
+            gestures.extend(recognizer.feed_point(
+                MOUSE_TOUCH_ID,
+                phase,
+                cursor,
+                now,
+                &game_config,
+            ));
+        }
+        // Dragged out of the window, so the release would never be seen
+        (Some(_), None) => {
+            recognizer.feed_point(
+                MOUSE_TOUCH_ID,
+                TouchPhase::Canceled,
+                Vec2::ZERO,
+                now,
+                &game_config,
+            );
+        }
+        (None, _) => {}
+    }
+
+    for gesture in gestures {
+        action_state.press(gesture.action());
+    }
+
+    // Keeping the finger down after a swipe down holds the slide, like holding the key
+    if recognizer.held_swipe() == Some(Gesture::SwipeDown) {
+        action_state.hold(Action::Slide);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(phase: TouchPhase, x: f32, y: f32) -> TouchInput {
+        TouchInput {
+            phase,
+            position: Vec2::new(x, y),
+            window: Entity::PLACEHOLDER,
+            force: None,
+            id: 0,
+        }
+    }
+
+    /// Touches down at the origin at `start`, drags to `(x, y)` at `end` and lifts off,
+    /// returning every gesture recognized on the way
+    fn stroke(
+        recognizer: &mut SwipeRecognizer,
+        x: f32,
+        y: f32,
+        start: f32,
+        end: f32,
+    ) -> Vec<Gesture> {
+        let game_config = GameConfig::default();
+        [
+            (touch(TouchPhase::Started, 0.0, 0.0), start),
+            (touch(TouchPhase::Moved, x, y), end),
+            (touch(TouchPhase::Ended, x, y), end),
+        ]
+        .iter()
+        .filter_map(|(touch, now)| recognizer.feed(touch, *now, &game_config))
+        .collect()
+    }
+
+    #[test]
+    fn swipes_in_all_four_directions() {
+        let mut recognizer = SwipeRecognizer::default();
+        // Screen y grows downwards, so up is negative
+        assert_eq!(
+            stroke(&mut recognizer, -100.0, 10.0, 0.0, 0.1),
+            [Gesture::SwipeLeft]
+        );
+        assert_eq!(
+            stroke(&mut recognizer, 100.0, -10.0, 1.0, 1.1),
+            [Gesture::SwipeRight]
+        );
+        assert_eq!(
+            stroke(&mut recognizer, 10.0, -100.0, 2.0, 2.1),
+            [Gesture::SwipeUp]
+        );
+        assert_eq!(
+            stroke(&mut recognizer, -10.0, 100.0, 3.0, 3.1),
+            [Gesture::SwipeDown]
+        );
+    }
+
+    #[test]
+    fn short_or_slow_drags_are_not_swipes() {
+        let game_config = GameConfig::default();
+        let mut recognizer = SwipeRecognizer::default();
+
+        // Fast, but shorter than the minimum distance and too long for a tap
+        let short = game_config.swipe_min_distance - 10.0;
+        assert!(stroke(&mut recognizer, short, 0.0, 0.0, 0.01).is_empty());
+
+        // Far enough, but slower than the minimum velocity
+        let far = game_config.swipe_min_distance * 2.0;
+        let slow = far / game_config.swipe_min_velocity * 2.0;
+        assert!(stroke(&mut recognizer, far, 0.0, 1.0, 1.0 + slow).is_empty());
+
+        // Just over both thresholds
+        let quick = far / game_config.swipe_min_velocity * 0.5;
+        assert_eq!(
+            stroke(&mut recognizer, far, 0.0, 2.0, 2.0 + quick),
+            [Gesture::SwipeRight]
+        );
+    }
+
+    #[test]
+    fn touch_that_barely_moves_is_a_tap() {
+        let mut recognizer = SwipeRecognizer::default();
+        assert_eq!(stroke(&mut recognizer, 3.0, -2.0, 0.0, 0.1), [Gesture::Tap]);
+    }
+
+    #[test]
+    fn second_tap_within_the_window_is_a_double_tap() {
+        let window = GameConfig::default().double_tap_window;
+        let mut recognizer = SwipeRecognizer::default();
+
+        assert_eq!(stroke(&mut recognizer, 0.0, 0.0, 0.0, 0.05), [Gesture::Tap]);
+        let second = 0.05 + window * 0.5;
+        assert_eq!(
+            stroke(&mut recognizer, 0.0, 0.0, second - 0.05, second),
+            [Gesture::DoubleTap]
+        );
+        // A double-tap starts over rather than chaining into another one
+        let third = second + window * 0.5;
+        assert_eq!(
+            stroke(&mut recognizer, 0.0, 0.0, third - 0.05, third),
+            [Gesture::Tap]
+        );
+    }
+
+    #[test]
+    fn second_tap_after_the_window_is_another_tap() {
+        let window = GameConfig::default().double_tap_window;
+        let mut recognizer = SwipeRecognizer::default();
+
+        assert_eq!(stroke(&mut recognizer, 0.0, 0.0, 0.0, 0.05), [Gesture::Tap]);
+        let late = 0.05 + window * 2.0;
+        assert_eq!(
+            stroke(&mut recognizer, 0.0, 0.0, late - 0.05, late),
+            [Gesture::Tap]
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use crate::game::input::PlayerInput;
+use crate::game::player::Player;
+use crate::resources::game_config::GameConfig;
+use crate::resources::profile::PlayerProfile;
+use bevy::prelude::*;
+
+/// Active hoverboard: absorbs one lethal hit until it expires
+#[derive(Component)]
+pub struct Hoverboard {
+    pub timer: Timer,
+    pub board: Entity, // Visual board spawned under the player
+}
+
+/// Grace period after the board breaks, during which collisions are ignored
+#[derive(Component)]
+pub struct Invulnerable {
+    pub timer: Timer,
+}
+
+#[derive(Component)]
+pub struct HoverboardUI;
+
+// How often the player model toggles visibility while invulnerable
+const BLINK_INTERVAL: f32 = 0.1;
+
+pub fn activate_hoverboard(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut profile: ResMut<PlayerProfile>,
+    player_input: Res<PlayerInput>,
+    game_config: Res<GameConfig>,
+    player_query: Query<Entity, (With<Player>, Without<Hoverboard>)>,
+) {
+    if !player_input.activate_hoverboard || profile.hoverboards == 0 {
+        return;
+    }
+
+    let Ok(player_entity) = player_query.single() else {
+        return; // No player, or a board is already active
+    };
+
+    profile.hoverboards -= 1;
+    profile.save();
+
+    let board_material = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.1, 0.8, 0.9),
+        emissive: LinearRgba::new(0.0, 0.4, 0.5, 1.0),
+        ..default()
+    });
+    let board_mesh = meshes.add(Cuboid::new(0.5, 0.06, 1.1));
+
+    // Board sits under the feet (capsule bottom is 0.8 below the player's center)
+    let board = commands
+        .spawn((
+            Mesh3d(board_mesh),
+            MeshMaterial3d(board_material),
+            Transform::from_xyz(0.0, -0.75, 0.0),
+            ChildOf(player_entity),
+        ))
+        .id();
+
+    commands.entity(player_entity).insert(Hoverboard {
+        timer: Timer::from_seconds(game_config.hoverboard_duration, TimerMode::Once),
+        board,
+    });
+}
+
+pub fn tick_hoverboard(
+    mut commands: Commands,
+    mut player_query: Query<(Entity, &mut Hoverboard), With<Player>>,
+    time: Res<Time>,
+) {
+    for (entity, mut hoverboard) in player_query.iter_mut() {
+        hoverboard.timer.tick(time.delta());
+        if hoverboard.timer.just_finished() {
+            commands.entity(hoverboard.board).despawn();
+            commands.entity(entity).remove::<Hoverboard>();
+        }
+    }
+}
+
+/// Breaks the board instead of ending the run: the player blinks for a short
+/// invulnerability window, and the caller smashes whatever was hit
+pub fn absorb_hit(
+    commands: &mut Commands,
+    player_entity: Entity,
+    hoverboard: &Hoverboard,
+    game_config: &GameConfig,
+) {
+    commands.entity(hoverboard.board).despawn();
+    commands
+        .entity(player_entity)
+        .remove::<Hoverboard>()
+        .insert(Invulnerable {
+            timer: Timer::from_seconds(game_config.invulnerability_duration, TimerMode::Once),
+        });
+}
+
+pub fn blink_invulnerable(
+    mut commands: Commands,
+    mut player_query: Query<(Entity, &mut Invulnerable, &mut Visibility), With<Player>>,
+    time: Res<Time>,
+) {
+    for (entity, mut invulnerable, mut visibility) in player_query.iter_mut() {
+        invulnerable.timer.tick(time.delta());
+
+        if invulnerable.timer.is_finished() {
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).remove::<Invulnerable>();
+            continue;
+        }
+
+        let blink_on = (invulnerable.timer.elapsed_secs() / BLINK_INTERVAL) as u32 % 2 == 0;
+        *visibility = if blink_on {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+/// Drops any active board or blink when the run ends so the next run starts clean
+pub fn clear_hoverboard_on_game_over(
+    mut commands: Commands,
+    mut player_query: Query<(Entity, Option<&Hoverboard>, &mut Visibility), With<Player>>,
+) {
+    for (entity, hoverboard, mut visibility) in player_query.iter_mut() {
+        if let Some(hoverboard) = hoverboard {
+            commands.entity(hoverboard.board).despawn();
+        }
+        *visibility = Visibility::Inherited;
+        commands
+            .entity(entity)
+            .remove::<Hoverboard>()
+            .remove::<Invulnerable>();
+    }
+}
+
+pub fn setup_hoverboard_ui(mut commands: Commands) {
+    commands.spawn((
+        HoverboardUI,
+        Text::new(""),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.1, 0.8, 0.9)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(50.0),
+            right: Val::Px(20.0),
+            ..default()
+        },
+    ));
+}
+
+pub fn update_hoverboard_ui(
+    profile: Res<PlayerProfile>,
+    player_query: Query<&Hoverboard, With<Player>>,
+    mut query: Query<&mut Text, With<HoverboardUI>>,
+) {
+    let status = match player_query.single() {
+        Ok(hoverboard) => format!(
+            "Hoverboard: {:.0}s",
+            hoverboard.timer.remaining_secs().ceil()
+        ),
+        Err(_) => format!("Hoverboards: {} (B)", profile.hoverboards),
+    };
+
+    for mut text in query.iter_mut() {
+        **text = status.clone();
+    }
+}
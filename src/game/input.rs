@@ -1,22 +1,208 @@
+use crate::game::action_map::{Action, ActionState};
+use crate::resources::game_config::GameConfig;
 use bevy::prelude::*;
 
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BufferedAction {
+    Jump,
+    Slide,
+}
+
+/// Recent presses kept around until they can be acted on, so a jump pressed just
+/// before landing still fires
+#[derive(Resource, Default)]
+pub struct InputBuffer {
+    actions: Vec<(BufferedAction, f32)>, // Action and the elapsed seconds it was pressed at
+}
+
+impl InputBuffer {
+    pub fn push(&mut self, action: BufferedAction, at: f32) {
+        self.actions.push((action, at));
+    }
+
+    /// Takes `action` if it was pressed no more than `window` seconds ago
+    pub fn consume(&mut self, action: BufferedAction, now: f32, window: f32) -> bool {
+        let Some(index) = self
+            .actions
+            .iter()
+            .rposition(|&(buffered, at)| buffered == action && now - at <= window)
+        else {
+            return false;
+        };
+        self.actions.remove(index);
+        true
+    }
+
+    pub fn expire(&mut self, now: f32, window: f32) {
+        self.actions.retain(|&(_, at)| now - at <= window);
+    }
+
+    pub fn clear(&mut self) {
+        self.actions.clear();
+    }
+}
+
 #[derive(Resource, Default)]
 pub struct PlayerInput {
     pub move_left: bool,
     pub move_right: bool,
     pub jump: bool,
     pub slide: bool,
+    pub slide_held: bool, // Keeps a slide going until released or it hits its maximum
+    pub activate_hoverboard: bool,
 }
 
-pub fn handle_keyboard_input(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+/// Translates this frame's actions, from any device, into player input
+pub fn update_player_input(
+    action_state: Res<ActionState>,
     mut player_input: ResMut<PlayerInput>,
+    mut last_jump_press: Local<Option<f32>>,
+    game_config: Res<GameConfig>,
+    time: Res<Time>,
 ) {
-    player_input.move_left = keyboard_input.just_pressed(KeyCode::ArrowLeft)
-        || keyboard_input.just_pressed(KeyCode::KeyA);
-    player_input.move_right = keyboard_input.just_pressed(KeyCode::ArrowRight)
-        || keyboard_input.just_pressed(KeyCode::KeyD);
-    player_input.jump = keyboard_input.just_pressed(KeyCode::Space);
-    player_input.slide = keyboard_input.just_pressed(KeyCode::ArrowDown)
-        || keyboard_input.just_pressed(KeyCode::KeyS);
+    player_input.move_left = action_state.just_pressed(Action::MoveLeft);
+    player_input.move_right = action_state.just_pressed(Action::MoveRight);
+    player_input.jump = action_state.just_pressed(Action::Jump);
+    player_input.slide = action_state.just_pressed(Action::Slide);
+    player_input.slide_held = action_state.pressed(Action::Slide);
+
+    // Double-tapping jump is an alternative to the dedicated hoverboard binding
+    let mut double_tapped = false;
+    if player_input.jump {
+        let now = time.elapsed_secs();
+        if let Some(previous) = *last_jump_press {
+            double_tapped = now - previous <= game_config.double_tap_window;
+        }
+        *last_jump_press = if double_tapped { None } else { Some(now) };
+    }
+    // The second tap is spent on the board rather than jumping again
+    if double_tapped {
+        player_input.jump = false;
+    }
+    player_input.activate_hoverboard =
+        double_tapped || action_state.just_pressed(Action::Hoverboard);
+}
+
+/// Timestamps this frame's jump and slide presses into the buffer
+pub fn buffer_player_input(
+    player_input: Res<PlayerInput>,
+    mut input_buffer: ResMut<InputBuffer>,
+    game_config: Res<GameConfig>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs();
+    input_buffer.expire(now, game_config.input_buffer_window);
+
+    if player_input.jump {
+        input_buffer.push(BufferedAction::Jump, now);
+    }
+    if player_input.slide {
+        input_buffer.push(BufferedAction::Slide, now);
+    }
+}
+
+/// Drops presses left over from the game over screen so a restart doesn't start with a jump
+pub fn clear_input_buffer(mut input_buffer: ResMut<InputBuffer>) {
+    input_buffer.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::events::{Jumped, Landed, Slid};
+    use crate::game::player::{AnimationState, GroundContact, Player};
+    use crate::game::systems::handle_slide_jump_input;
+    use avian3d::prelude::LinearVelocity;
+    use std::time::Duration;
+
+    // Step between frames, well inside the buffer window
+    const FRAME: f32 = 0.05;
+
+    #[test]
+    fn consume_takes_a_press_once() {
+        let mut buffer = InputBuffer::default();
+        buffer.push(BufferedAction::Jump, 1.0);
+
+        assert!(!buffer.consume(BufferedAction::Slide, 1.05, 0.15));
+        assert!(buffer.consume(BufferedAction::Jump, 1.05, 0.15));
+        assert!(!buffer.consume(BufferedAction::Jump, 1.05, 0.15));
+    }
+
+    #[test]
+    fn presses_older_than_the_window_are_dropped() {
+        let mut buffer = InputBuffer::default();
+        buffer.push(BufferedAction::Jump, 1.0);
+        assert!(!buffer.consume(BufferedAction::Jump, 1.2, 0.15));
+
+        buffer.push(BufferedAction::Slide, 1.0);
+        buffer.push(BufferedAction::Jump, 1.1);
+        buffer.expire(1.2, 0.15);
+        assert!(!buffer.consume(BufferedAction::Slide, 1.0, 0.15));
+        assert!(buffer.consume(BufferedAction::Jump, 1.2, 0.15));
+    }
+
+    fn jump_app() -> App {
+        let mut app = App::new();
+        app.insert_resource(Time::<()>::default())
+            .insert_resource(GameConfig::default())
+            .init_resource::<PlayerInput>()
+            .init_resource::<InputBuffer>()
+            .add_message::<Jumped>()
+            .add_message::<Slid>()
+            .add_message::<Landed>()
+            .add_systems(
+                Update,
+                (buffer_player_input, handle_slide_jump_input).chain(),
+            );
+        app
+    }
+
+    fn step(app: &mut App, input: PlayerInput) {
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(FRAME));
+        app.insert_resource(input);
+        app.update();
+    }
+
+    #[test]
+    fn jump_pressed_before_landing_fires_on_contact() {
+        let mut app = jump_app();
+        let player = app
+            .world_mut()
+            .spawn((
+                Player,
+                AnimationState::Falling,
+                LinearVelocity::ZERO,
+                GroundContact {
+                    grounded: false,
+                    last_grounded_at: f32::NEG_INFINITY,
+                    platform_velocity: 0.0,
+                    on_train: false,
+                },
+                Transform::default(),
+            ))
+            .id();
+
+        // Pressed in the air, too late for coyote time
+        step(
+            &mut app,
+            PlayerInput {
+                jump: true,
+                ..default()
+            },
+        );
+        assert!(app.world().get::<AnimationState>(player) == Some(&AnimationState::Falling));
+
+        // Touching down goes through landing, where the buffered press is taken
+        app.world_mut()
+            .get_mut::<GroundContact>(player)
+            .unwrap()
+            .grounded = true;
+        step(&mut app, PlayerInput::default());
+        step(&mut app, PlayerInput::default());
+
+        assert!(app.world().get::<AnimationState>(player) == Some(&AnimationState::Jumping));
+        assert!(app.world().get::<LinearVelocity>(player).unwrap().y > 0.0);
+    }
 }
@@ -0,0 +1,379 @@
+use crate::game::action_map::{Action, ActionMap, ActionState};
+use crate::game::barricade::ObstacleType;
+use crate::game::events::{
+    CauseKind, CoinCollected, Jumped, LaneChanged, NearMiss, ObstacleCleared, PlayerDied, Slid,
+};
+use crate::game::player::{GroundContact, Player};
+use crate::resources::missions::{ACTIVE_MISSIONS, Goal, MissionBook, Scope};
+use crate::resources::profile::PlayerProfile;
+use bevy::prelude::*;
+
+// Seconds a popup stays on screen
+const POPUP_DURATION: f32 = 2.5;
+// A mission's progress pops up each time it passes another quarter of the target
+const PROGRESS_STEPS: f32 = 4.0;
+
+/// Progress towards a goal, counted from gameplay events and the distance run
+#[derive(Message, Clone, Copy, Debug)]
+pub struct GoalProgress {
+    pub goal: Goal,
+    pub amount: f32,
+}
+
+/// Text for a popup: mission progress, a finished mission or set, or a new achievement
+#[derive(Message, Clone, Debug)]
+pub struct MissionNotice {
+    pub text: String,
+}
+
+/// Totals for the run in progress, for goals that have to be reached in one run
+#[derive(Resource, Default)]
+pub struct RunTally {
+    totals: [f32; Goal::ALL.len()],
+}
+
+/// Achievements screen opened from the game over screen
+#[derive(Resource, Default)]
+pub struct AchievementGallery {
+    pub open: bool,
+}
+
+#[derive(Component)]
+pub struct GalleryText;
+
+/// Column the popups stack up in
+#[derive(Component)]
+pub struct PopupStack;
+
+#[derive(Component)]
+pub struct MissionPopup {
+    timer: Timer,
+}
+
+/// Run condition keeping game over input from firing underneath the gallery
+pub fn gallery_closed(gallery: Res<AchievementGallery>) -> bool {
+    !gallery.open
+}
+
+pub fn tally_gameplay_events(
+    mut coins: MessageReader<CoinCollected>,
+    mut jumps: MessageReader<Jumped>,
+    mut slides: MessageReader<Slid>,
+    mut lane_changes: MessageReader<LaneChanged>,
+    mut near_misses: MessageReader<NearMiss>,
+    mut cleared: MessageReader<ObstacleCleared>,
+    mut deaths: MessageReader<PlayerDied>,
+    mut progress: MessageWriter<GoalProgress>,
+) {
+    let mut count = |goal, amount: usize| {
+        if amount > 0 {
+            progress.write(GoalProgress {
+                goal,
+                amount: amount as f32,
+            });
+        }
+    };
+
+    count(Goal::Coins, coins.read().count());
+    count(Goal::Jumps, jumps.read().count());
+    count(Goal::Slides, slides.read().count());
+    // Being steered out of a closing lane doesn't count
+    count(
+        Goal::LaneChanges,
+        lane_changes
+            .read()
+            .filter(|lane_change| lane_change.cause.kind == CauseKind::Player)
+            .count(),
+    );
+    count(Goal::NearMisses, near_misses.read().count());
+    for clear in cleared.read() {
+        match clear.cause.kind {
+            CauseKind::Barricade(ObstacleType::JumpOver) => count(Goal::JumpOver, 1),
+            CauseKind::Barricade(ObstacleType::SlideUnder) => count(Goal::SlideUnder, 1),
+            CauseKind::Barricade(ObstacleType::LowTunnel) => count(Goal::Tunnels, 1),
+            _ => {}
+        }
+    }
+    count(Goal::Runs, deaths.read().count().min(1));
+}
+
+/// Counts the metres run, and those run on train roofs
+pub fn tally_distance(
+    player_query: Query<(&Transform, &GroundContact), With<Player>>,
+    mut last_z: Local<Option<f32>>,
+    mut progress: MessageWriter<GoalProgress>,
+) {
+    let Ok((transform, ground_contact)) = player_query.single() else {
+        return;
+    };
+    let z = transform.translation.z;
+    // Being put back at the start for a new run isn't running
+    let run = last_z.map_or(0.0, |last_z| (z - last_z).max(0.0));
+    *last_z = Some(z);
+
+    if run > 0.0 {
+        progress.write(GoalProgress {
+            goal: Goal::Distance,
+            amount: run,
+        });
+        if ground_contact.on_train {
+            progress.write(GoalProgress {
+                goal: Goal::RoofDistance,
+                amount: run,
+            });
+        }
+    }
+}
+
+/// Adds progress to the active missions and lifetime totals, completing missions, sets
+/// and achievements as their targets are reached
+pub fn advance_missions(
+    mut progress: MessageReader<GoalProgress>,
+    mut run_tally: ResMut<RunTally>,
+    mut profile: ResMut<PlayerProfile>,
+    mission_book: Res<MissionBook>,
+    mut notices: MessageWriter<MissionNotice>,
+) {
+    let mut completed = false;
+    for &GoalProgress { goal, amount } in progress.read() {
+        run_tally.totals[goal.index()] += amount;
+        profile.add_stat(goal, amount);
+        let run_total = run_tally.totals[goal.index()];
+
+        let active = mission_book.active(profile.mission_sets);
+        for (slot, mission) in active.iter().enumerate() {
+            let target = mission.target as f32;
+            let before = profile.mission_progress[slot];
+            if mission.goal != goal || before >= target {
+                continue;
+            }
+            let after = match mission.scope {
+                Scope::Run => run_total,
+                Scope::Total => before + amount,
+            }
+            .min(target);
+            profile.mission_progress[slot] = after;
+
+            let text = if after >= target {
+                completed = true;
+                format!("Mission complete: {}", mission.name)
+            } else if (after / target * PROGRESS_STEPS).floor()
+                > (before / target * PROGRESS_STEPS).floor()
+            {
+                format!("{}: {}/{}", mission.name, after as u32, mission.target)
+            } else {
+                continue;
+            };
+            notices.write(MissionNotice { text });
+        }
+
+        // With the whole set done, the multiplier goes up and the next set is dealt
+        let set_done = active
+            .iter()
+            .enumerate()
+            .all(|(slot, mission)| profile.mission_progress[slot] >= mission.target as f32);
+        if !active.is_empty() && set_done {
+            profile.mission_sets += 1;
+            profile.mission_progress = [0.0; ACTIVE_MISSIONS];
+            notices.write(MissionNotice {
+                text: format!(
+                    "Mission set complete! Score multiplier x{}",
+                    profile.multiplier()
+                ),
+            });
+        }
+
+        for achievement in &mission_book.achievements {
+            if achievement.goal != goal || profile.has_achievement(&achievement.id) {
+                continue;
+            }
+            let total = match achievement.scope {
+                Scope::Run => run_total,
+                Scope::Total => profile.stat(goal),
+            };
+            if total >= achievement.target as f32 {
+                profile.unlock_achievement(&achievement.id);
+                completed = true;
+                notices.write(MissionNotice {
+                    text: format!("Achievement unlocked: {}", achievement.name),
+                });
+            }
+        }
+    }
+
+    // Progress is otherwise saved with the banked coins at the end of the run
+    if completed {
+        profile.save();
+    }
+}
+
+/// Starts a new run's tally, and drops progress on one-run missions left unfinished
+pub fn reset_run_tally(
+    mut run_tally: ResMut<RunTally>,
+    mut profile: ResMut<PlayerProfile>,
+    mission_book: Res<MissionBook>,
+) {
+    *run_tally = RunTally::default();
+    let active = mission_book.active(profile.mission_sets);
+    for (slot, mission) in active.iter().enumerate() {
+        if mission.scope == Scope::Run && profile.mission_progress[slot] < mission.target as f32 {
+            profile.mission_progress[slot] = 0.0;
+        }
+    }
+}
+
+pub fn setup_mission_popups(mut commands: Commands) {
+    commands.spawn((
+        PopupStack,
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(90.0),
+            width: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(6.0),
+            ..default()
+        },
+    ));
+}
+
+pub fn show_mission_popups(
+    mut commands: Commands,
+    mut notices: MessageReader<MissionNotice>,
+    stack_query: Query<Entity, With<PopupStack>>,
+) {
+    let Ok(stack) = stack_query.single() else {
+        return;
+    };
+    for notice in notices.read() {
+        commands.entity(stack).with_child((
+            MissionPopup {
+                timer: Timer::from_seconds(POPUP_DURATION, TimerMode::Once),
+            },
+            Text::new(notice.text.clone()),
+            TextFont {
+                font_size: 22.0,
+                ..default()
+            },
+            TextColor(Color::srgb(1.0, 0.85, 0.0)),
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            Node {
+                padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                ..default()
+            },
+        ));
+    }
+}
+
+pub fn expire_mission_popups(
+    mut commands: Commands,
+    mut popup_query: Query<(Entity, &mut MissionPopup)>,
+    time: Res<Time>,
+) {
+    for (entity, mut popup) in popup_query.iter_mut() {
+        if popup.timer.tick(time.delta()).is_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+pub fn handle_achievement_gallery(
+    mut commands: Commands,
+    mut gallery: ResMut<AchievementGallery>,
+    action_state: Res<ActionState>,
+    text_query: Query<Entity, With<GalleryText>>,
+) {
+    if !gallery.open {
+        if action_state.just_pressed(Action::Achievements) {
+            gallery.open = true;
+            spawn_gallery_ui(&mut commands);
+        }
+        return;
+    }
+
+    if action_state.just_pressed(Action::Back) || action_state.just_pressed(Action::Achievements) {
+        gallery.open = false;
+        for entity in text_query.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn spawn_gallery_ui(commands: &mut Commands) {
+    commands.spawn((
+        GalleryText,
+        Text::new(""),
+        TextFont {
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(60.0),
+            left: Val::Px(20.0),
+            padding: UiRect::all(Val::Px(12.0)),
+            ..default()
+        },
+    ));
+}
+
+pub fn update_gallery_ui(
+    profile: Res<PlayerProfile>,
+    mission_book: Res<MissionBook>,
+    action_map: Res<ActionMap>,
+    mut query: Query<&mut Text, With<GalleryText>>,
+) {
+    for mut text in query.iter_mut() {
+        let mut lines = vec![
+            format!(
+                "ACHIEVEMENTS - {} to close",
+                action_map.describe(Action::Back)
+            ),
+            format!("Missions (score multiplier x{}):", profile.multiplier()),
+        ];
+
+        let active = mission_book.active(profile.mission_sets);
+        for (slot, mission) in active.iter().enumerate() {
+            let progress = profile.mission_progress[slot] as u32;
+            let mark = if progress >= mission.target { "x" } else { " " };
+            lines.push(format!(
+                "  [{mark}] {} ({progress}/{})",
+                mission.name, mission.target
+            ));
+        }
+
+        let unlocked = mission_book
+            .achievements
+            .iter()
+            .filter(|achievement| profile.has_achievement(&achievement.id))
+            .count();
+        lines.push(format!(
+            "Achievements ({unlocked}/{}):",
+            mission_book.achievements.len()
+        ));
+        for achievement in &mission_book.achievements {
+            let mark = if profile.has_achievement(&achievement.id) {
+                "x"
+            } else {
+                " "
+            };
+            lines.push(format!("  [{mark}] {}", achievement.name));
+        }
+
+        **text = lines.join("\n");
+    }
+}
+
+/// Makes sure the gallery never carries over into the next run
+pub fn close_achievement_gallery(
+    mut commands: Commands,
+    mut gallery: ResMut<AchievementGallery>,
+    text_query: Query<Entity, With<GalleryText>>,
+) {
+    gallery.open = false;
+    for entity in text_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -1,10 +1,30 @@
+pub mod action_map;
+pub mod audio;
 pub mod barricade;
+pub mod bridge;
 pub mod camera;
+pub mod chaser;
 pub mod coin;
+pub mod collision;
+pub mod daily;
+pub mod day_cycle;
+pub mod events;
 pub mod game_state;
+pub mod gestures;
+pub mod hoverboard;
 pub mod input;
+pub mod missions;
+pub mod music;
+pub mod path;
 pub mod player;
 pub mod props;
+pub mod replay;
+pub mod settings;
+pub mod shop;
+pub mod signal;
 pub mod systems;
+pub mod theme;
 pub mod track;
+pub mod track_mesh;
+pub mod track_switch;
 pub mod train;
@@ -0,0 +1,505 @@
+use crate::game::audio::{Envelope, SoundCue, Synth, SynthDecoder, VolumeCategory, Waveform};
+use crate::game::game_state::GameState;
+use crate::game::hoverboard::Hoverboard;
+use crate::game::player::Player;
+use crate::resources::game_config::GameConfig;
+use crate::resources::profile::PlayerProfile;
+use crate::resources::theme::{MusicStyle, Themes};
+use bevy::audio::{Source, Volume};
+use bevy::prelude::*;
+use std::time::Duration;
+
+// Semitones above the root for each degree of the scale
+const MINOR_SCALE: [f32; 7] = [0.0, 2.0, 3.0, 5.0, 7.0, 8.0, 10.0];
+const MAJOR_SCALE: [f32; 7] = [0.0, 2.0, 4.0, 5.0, 7.0, 9.0, 11.0];
+// Scale degree each chord of the looping progression is built on
+const PROGRESSION: [i32; 4] = [0, 5, 3, 4];
+// Eighth notes played over each chord
+const STEPS_PER_CHORD: i32 = 4;
+// How fast layers fade towards the mix, and old themes' music out, in level per second
+const LAYER_FADE_RATE: f32 = 0.5;
+const THEME_FADE_RATE: f32 = 0.4;
+// Danger falls off at this rate per second, and adds this much to the intensity at most
+const DANGER_DECAY: f32 = 0.25;
+const DANGER_WEIGHT: f32 = 0.4;
+const STING_GAIN: f32 = 0.8;
+
+/// One looping part of the music, faded in and out as the run gets more intense
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MusicLayer {
+    Bass,
+    Drums,
+    Lead,
+    Power, // Only while a hoverboard is ridden
+}
+
+impl MusicLayer {
+    pub const ALL: [MusicLayer; 4] = [
+        MusicLayer::Bass,
+        MusicLayer::Drums,
+        MusicLayer::Lead,
+        MusicLayer::Power,
+    ];
+
+    /// Level the layer heads for at `intensity`, from 0 to 1
+    fn target(self, intensity: f32, power_up: bool) -> f32 {
+        let fade_in = |from: f32, to: f32| ((intensity - from) / (to - from)).clamp(0.0, 1.0);
+        match self {
+            MusicLayer::Bass => 1.0,
+            MusicLayer::Drums => fade_in(0.1, 0.4),
+            MusicLayer::Lead => fade_in(0.5, 0.85),
+            MusicLayer::Power => {
+                if power_up {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+
+    /// The layer's own loudness, before the mix and volume settings
+    fn gain(self) -> f32 {
+        match self {
+            MusicLayer::Bass => 0.8,
+            MusicLayer::Drums => 0.7,
+            MusicLayer::Lead => 0.5,
+            MusicLayer::Power => 0.4,
+        }
+    }
+}
+
+/// What the music should be doing, worked out from the run without touching audio, so it
+/// can be checked headless.
+#[derive(Resource, Default)]
+pub struct MusicMix {
+    pub style: Option<MusicStyle>, // Music of the theme the player is running through
+    pub intensity: f32,            // 0 at the starting speed, 1 flat out or in danger
+    pub danger: f32,               // Raised by horns and stumbles, then falls away
+    pub power_up: bool,
+    crashed: bool, // From the crash until the run is over, so the layers fade out with it
+    levels: [f32; 4], // Current level of each layer, indexed by `MusicLayer`
+}
+
+impl MusicMix {
+    pub fn level(&self, layer: MusicLayer) -> f32 {
+        self.levels[layer as usize]
+    }
+}
+
+/// Sequence of synthesized notes played back to back; music layers loop one of these
+#[derive(Asset, TypePath, Clone, Debug)]
+pub struct Stem {
+    pub notes: Vec<Synth>,
+}
+
+impl Decodable for Stem {
+    type DecoderItem = f32;
+    type Decoder = StemDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        let first = self.notes.first().copied().unwrap_or(rest(Duration::ZERO));
+        StemDecoder {
+            notes: self.notes.clone(),
+            index: 0,
+            current: first.decoder(),
+        }
+    }
+}
+
+/// Renders a [`Stem`] by decoding each of its notes in turn
+pub struct StemDecoder {
+    notes: Vec<Synth>,
+    index: usize,
+    current: SynthDecoder,
+}
+
+impl Iterator for StemDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        loop {
+            if let Some(sample) = self.current.next() {
+                return Some(sample);
+            }
+            self.index += 1;
+            self.current = self.notes.get(self.index)?.decoder();
+        }
+    }
+}
+
+impl Source for StemDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.current.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.current.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.current.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(self.notes.iter().map(|note| note.duration).sum())
+    }
+}
+
+/// Frequency of scale `degree` above the style's root, `octave` octaves up
+fn pitch(style: MusicStyle, degree: i32, octave: i32) -> f32 {
+    let scale = if style.minor {
+        &MINOR_SCALE
+    } else {
+        &MAJOR_SCALE
+    };
+    let semitones =
+        scale[degree.rem_euclid(7) as usize] + 12.0 * (octave + degree.div_euclid(7)) as f32;
+    style.root * 2f32.powf(semitones / 12.0)
+}
+
+fn note(waveform: Waveform, frequency: f32, duration: Duration) -> Synth {
+    Synth {
+        waveform,
+        start_frequency: frequency,
+        end_frequency: frequency,
+        noise: 0.0,
+        envelope: Envelope::Pluck,
+        duration,
+    }
+}
+
+fn rest(duration: Duration) -> Synth {
+    // A sine at 0 Hz stays at zero
+    note(Waveform::Sine, 0.0, duration)
+}
+
+/// Writes two bars of `layer` over the chord progression, in the style's key and tempo
+fn compose(layer: MusicLayer, style: MusicStyle) -> Stem {
+    let step = Duration::from_secs_f32(30.0 / style.tempo);
+    let mut notes = Vec::new();
+
+    for chord in PROGRESSION {
+        for beat in 0..STEPS_PER_CHORD {
+            match layer {
+                MusicLayer::Bass => notes.push(match beat {
+                    1 => rest(step),
+                    3 => note(Waveform::Square, pitch(style, chord, 1), step),
+                    _ => note(Waveform::Square, pitch(style, chord, 0), step),
+                }),
+                MusicLayer::Drums => match beat {
+                    // Kick
+                    0 => notes.push(Synth {
+                        start_frequency: 150.0,
+                        end_frequency: 40.0,
+                        noise: 0.1,
+                        ..note(Waveform::Sine, 0.0, step)
+                    }),
+                    // Snare
+                    2 => notes.push(Synth {
+                        start_frequency: 220.0,
+                        end_frequency: 180.0,
+                        noise: 0.8,
+                        ..note(Waveform::Sine, 0.0, step)
+                    }),
+                    // Short hi-hat, then silence for the rest of the step
+                    _ => {
+                        notes.push(Synth {
+                            noise: 1.0,
+                            ..rest(step / 4)
+                        });
+                        notes.push(rest(step * 3 / 4));
+                    }
+                },
+                MusicLayer::Lead => {
+                    let arpeggio = [0, 2, 4, 7][beat as usize];
+                    notes.push(note(Waveform::Saw, pitch(style, chord + arpeggio, 2), step));
+                }
+                MusicLayer::Power => {
+                    let sparkle = [4, 7, 9, 7][beat as usize];
+                    notes.push(note(
+                        Waveform::Square,
+                        pitch(style, chord + sparkle, 3),
+                        step,
+                    ));
+                }
+            }
+        }
+    }
+
+    Stem { notes }
+}
+
+/// Falling phrase in the style's key that ends the music on game over
+fn compose_sting(style: MusicStyle) -> Stem {
+    let step = Duration::from_secs_f32(30.0 / style.tempo);
+    let mut notes: Vec<Synth> = [7, 4, 2]
+        .into_iter()
+        .map(|degree| note(Waveform::Saw, pitch(style, degree, 2), step))
+        .collect();
+    notes.push(note(Waveform::Saw, pitch(style, 0, 1), step * 4));
+    Stem { notes }
+}
+
+/// Stems composed for one music style
+struct StyleStems {
+    layers: [Handle<Stem>; 4], // Indexed by `MusicLayer`
+    sting: Handle<Stem>,
+}
+
+/// Stems for every music style heard so far, composed the first time each is needed
+#[derive(Resource, Default)]
+pub struct MusicBank {
+    styles: Vec<(MusicStyle, StyleStems)>,
+}
+
+impl MusicBank {
+    fn stems(&mut self, style: MusicStyle, assets: &mut Assets<Stem>) -> &StyleStems {
+        let index = match self.styles.iter().position(|(known, _)| *known == style) {
+            Some(index) => index,
+            None => {
+                let stems = StyleStems {
+                    layers: MusicLayer::ALL.map(|layer| assets.add(compose(layer, style))),
+                    sting: assets.add(compose_sting(style)),
+                };
+                self.styles.push((style, stems));
+                self.styles.len() - 1
+            }
+        };
+        &self.styles[index].1
+    }
+}
+
+/// One playing music layer; `fade` takes it out when the theme's music changes
+#[derive(Component)]
+pub struct MusicTrack {
+    pub layer: MusicLayer,
+    pub style: MusicStyle,
+    pub fade: f32,
+}
+
+/// Follows the run's speed, danger and power-ups, and the theme's music, into the mix
+pub fn update_music_mix(
+    mut mix: ResMut<MusicMix>,
+    mut cues: MessageReader<SoundCue>,
+    game_config: Res<GameConfig>,
+    themes: Res<Themes>,
+    game_state: Res<State<GameState>>,
+    time: Res<Time>,
+    player_query: Query<(&Transform, Has<Hoverboard>), With<Player>>,
+) {
+    let delta = time.delta_secs();
+    let player = player_query.single().ok();
+
+    // The player rather than the camera, which circles them during the crash replay
+    if let Some((player_transform, _)) = player {
+        mix.style = Some(themes.at(player_transform.translation.z).music);
+    }
+
+    mix.danger = (mix.danger - DANGER_DECAY * delta).max(0.0);
+    for cue in cues.read() {
+        match cue {
+            SoundCue::Horn { .. } => mix.danger = mix.danger.max(0.5),
+            SoundCue::Stumble => mix.danger = 1.0,
+            SoundCue::Crash => mix.crashed = true,
+            _ => {}
+        }
+    }
+    let playing = *game_state.get() == GameState::Playing;
+    if !playing {
+        mix.crashed = false; // The game over screen keeps the layers down from here
+    }
+
+    let speed_range = (game_config.music_top_speed - game_config.base_speed).max(1.0);
+    let speed = (game_config.current_speed - game_config.base_speed) / speed_range;
+    mix.intensity = (speed + mix.danger * DANGER_WEIGHT).clamp(0.0, 1.0);
+    mix.power_up = player.is_some_and(|(_, hoverboard)| hoverboard);
+
+    for layer in MusicLayer::ALL {
+        // Everything fades out after a crash, leaving the sting
+        let target = if playing && !mix.crashed {
+            layer.target(mix.intensity, mix.power_up)
+        } else {
+            0.0
+        };
+        let level = &mut mix.levels[layer as usize];
+        *level += (target - *level).clamp(-LAYER_FADE_RATE * delta, LAYER_FADE_RATE * delta);
+    }
+}
+
+/// Plays the theme's music layers at the mix's levels, cross-fading when the theme's
+/// music changes. Does nothing when the app has no audio.
+pub fn play_music(
+    mut commands: Commands,
+    mut music_bank: ResMut<MusicBank>,
+    stems: Option<ResMut<Assets<Stem>>>,
+    mix: Res<MusicMix>,
+    profile: Res<PlayerProfile>,
+    time: Res<Time>,
+    mut track_query: Query<(Entity, &mut MusicTrack, Option<&mut AudioSink>)>,
+) {
+    let (Some(mut stems), Some(style)) = (stems, mix.style) else {
+        return;
+    };
+    let gain = VolumeCategory::Music.gain(&profile);
+    let fade_step = THEME_FADE_RATE * time.delta_secs();
+    let mut style_playing = false;
+
+    for (entity, mut track, sink) in track_query.iter_mut() {
+        if track.style == style {
+            style_playing = true;
+            track.fade = (track.fade + fade_step).min(1.0);
+        } else {
+            track.fade -= fade_step;
+            if track.fade <= 0.0 {
+                commands.entity(entity).despawn();
+                continue;
+            }
+        }
+
+        // The sink only appears once playback has started
+        if let Some(mut sink) = sink {
+            let level = track.layer.gain() * mix.level(track.layer) * track.fade;
+            sink.set_volume(Volume::Linear(level * gain));
+        }
+    }
+
+    if !style_playing {
+        let style_stems = music_bank.stems(style, &mut stems);
+        for layer in MusicLayer::ALL {
+            commands.spawn((
+                MusicTrack {
+                    layer,
+                    style,
+                    fade: 0.0,
+                },
+                AudioPlayer(style_stems.layers[layer as usize].clone()),
+                PlaybackSettings::LOOP.with_volume(Volume::Linear(0.0)),
+            ));
+        }
+    }
+}
+
+/// Plays the theme's sting over the crash as the music fades out
+pub fn play_game_over_sting(
+    mut commands: Commands,
+    mut cues: MessageReader<SoundCue>,
+    mut music_bank: ResMut<MusicBank>,
+    stems: Option<ResMut<Assets<Stem>>>,
+    mix: Res<MusicMix>,
+    profile: Res<PlayerProfile>,
+) {
+    let crashed = cues.read().filter(|cue| **cue == SoundCue::Crash).count() > 0;
+    if !crashed {
+        return;
+    }
+    let (Some(mut stems), Some(style)) = (stems, mix.style) else {
+        return;
+    };
+
+    let sting = music_bank.stems(style, &mut stems).sting.clone();
+    let volume = STING_GAIN * VolumeCategory::Music.gain(&profile);
+    commands.spawn((
+        AudioPlayer(sting),
+        PlaybackSettings::DESPAWN.with_volume(Volume::Linear(volume)),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::state::app::StatesPlugin;
+
+    // Long enough for any layer to fade all the way in or out
+    const SETTLE_SECONDS: u32 = 3;
+    const FRAMES_PER_SECOND: u32 = 10;
+
+    fn mix_app(current_speed: f32) -> App {
+        let mut app = App::new();
+        app.add_plugins(StatesPlugin)
+            .init_state::<GameState>()
+            .insert_resource(Time::<()>::default())
+            .insert_resource(GameConfig {
+                current_speed,
+                ..default()
+            })
+            .init_resource::<Themes>()
+            .init_resource::<MusicMix>()
+            .add_message::<SoundCue>()
+            .add_systems(Update, update_music_mix);
+        app.world_mut().spawn((Player, Transform::default()));
+        app
+    }
+
+    fn settle(app: &mut App) {
+        for _ in 0..SETTLE_SECONDS * FRAMES_PER_SECOND {
+            app.world_mut()
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(1.0 / FRAMES_PER_SECOND as f32));
+            app.update();
+        }
+    }
+
+    fn levels(app: &App) -> [f32; 4] {
+        let mix = app.world().resource::<MusicMix>();
+        MusicLayer::ALL.map(|layer| mix.level(layer))
+    }
+
+    #[test]
+    fn layers_join_in_as_speed_rises() {
+        let game_config = GameConfig::default();
+
+        let mut app = mix_app(game_config.base_speed);
+        settle(&mut app);
+        assert_eq!(levels(&app), [1.0, 0.0, 0.0, 0.0]);
+
+        let halfway = (game_config.base_speed + game_config.music_top_speed) / 2.0;
+        app.world_mut().resource_mut::<GameConfig>().current_speed = halfway;
+        settle(&mut app);
+        let [bass, drums, lead, _] = levels(&app);
+        assert_eq!((bass, drums), (1.0, 1.0));
+        assert!(lead == 0.0, "lead is {lead} at half speed");
+
+        app.world_mut().resource_mut::<GameConfig>().current_speed = game_config.music_top_speed;
+        settle(&mut app);
+        assert_eq!(levels(&app), [1.0, 1.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn power_layer_plays_while_a_hoverboard_is_ridden() {
+        let mut app = mix_app(GameConfig::default().base_speed);
+        let player = app
+            .world_mut()
+            .query_filtered::<Entity, With<Player>>()
+            .single(app.world())
+            .unwrap();
+        app.world_mut().entity_mut(player).insert(Hoverboard {
+            timer: Timer::from_seconds(30.0, TimerMode::Once),
+            board: Entity::PLACEHOLDER,
+        });
+        settle(&mut app);
+        assert!(app.world().resource::<MusicMix>().power_up);
+        assert_eq!(
+            app.world().resource::<MusicMix>().level(MusicLayer::Power),
+            1.0
+        );
+
+        app.world_mut().entity_mut(player).remove::<Hoverboard>();
+        settle(&mut app);
+        assert_eq!(
+            app.world().resource::<MusicMix>().level(MusicLayer::Power),
+            0.0
+        );
+    }
+
+    #[test]
+    fn crash_fades_every_layer_out() {
+        let mut app = mix_app(GameConfig::default().music_top_speed);
+        settle(&mut app);
+        assert_eq!(levels(&app), [1.0, 1.0, 1.0, 0.0]);
+
+        app.world_mut().write_message(SoundCue::Crash);
+        settle(&mut app);
+        assert_eq!(levels(&app), [0.0; 4]);
+    }
+}
@@ -0,0 +1,73 @@
+use crate::resources::daily::DailyRun;
+use crate::resources::game_config::GameConfig;
+use crate::resources::track_path::TrackPath;
+use bevy::prelude::*;
+
+/// Root of a hierarchy laid out in track space, drawn bent along the `TrackPath`
+#[derive(Component, Default)]
+pub struct OnPath;
+
+/// Straight track space transforms of everything bent this frame, put back before the next
+#[derive(Resource, Default)]
+pub struct StraightTransforms(Vec<(Entity, GlobalTransform)>);
+
+// How far past the camera the path is integrated, beyond anything that spawns ahead
+const PATH_LOOKAHEAD: f32 = 600.0;
+
+pub fn extend_track_path(
+    mut track_path: ResMut<TrackPath>,
+    camera_query: Query<&Transform, With<Camera3d>>,
+    game_config: Res<GameConfig>,
+) {
+    if let Ok(camera_transform) = camera_query.single() {
+        track_path.extend_to(
+            camera_transform.translation.z + PATH_LOOKAHEAD,
+            &game_config,
+        );
+    }
+}
+
+/// Moves everything on the path from track space to its place in the world, after
+/// transforms propagate and before visibility and rendering read them.
+///
+/// Each entity bends around its own position, so long trains articulate car by car.
+pub fn bend_onto_path(
+    track_path: Res<TrackPath>,
+    mut straight: ResMut<StraightTransforms>,
+    root_query: Query<Entity, (With<OnPath>, Without<ChildOf>)>,
+    children_query: Query<&Children>,
+    mut global_query: Query<&mut GlobalTransform>,
+) {
+    straight.0.clear();
+    for root in root_query.iter() {
+        for entity in std::iter::once(root).chain(children_query.iter_descendants(root)) {
+            if let Ok(mut global_transform) = global_query.get_mut(entity) {
+                straight.0.push((entity, *global_transform));
+                *global_transform = track_path.bend(&global_transform);
+            }
+        }
+    }
+}
+
+/// Puts track space transforms back at the start of the frame, so gameplay and
+/// physics never see the bent ones
+pub fn restore_straight_transforms(
+    mut straight: ResMut<StraightTransforms>,
+    mut global_query: Query<&mut GlobalTransform>,
+) {
+    for (entity, straight_transform) in straight.0.drain(..) {
+        if let Ok(mut global_transform) = global_query.get_mut(entity) {
+            *global_transform = straight_transform;
+        }
+    }
+}
+
+/// Lays a fresh path for the next run; the daily run always gets the day's seed
+pub fn reset_track_path(mut track_path: ResMut<TrackPath>, daily_run: Res<DailyRun>) {
+    let seed = if daily_run.active {
+        daily_run.seed()
+    } else {
+        track_path.seed().wrapping_mul(0x2C1B_3C6D).wrapping_add(1)
+    };
+    *track_path = TrackPath::new(seed);
+}
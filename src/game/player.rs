@@ -1,26 +1,82 @@
+use crate::game::path::OnPath;
+use crate::resources::game_config::GameConfig;
+use crate::resources::lane_layout::LaneLayout;
 use avian3d::prelude::*;
 use bevy::prelude::*;
 
 #[derive(Component)]
 pub struct Player;
 
+/// Lane the player is in, or heading to; see `LaneLayout` for the numbering
 #[derive(Component, Clone, Copy, PartialEq)]
-pub enum CurrentTrack {
-    Left,
-    Middle,
-    Right,
-}
+pub struct CurrentTrack(pub u8);
 
 impl CurrentTrack {
-    pub fn as_index(&self) -> u8 {
-        match self {
-            CurrentTrack::Left => 0,
-            CurrentTrack::Middle => 1,
-            CurrentTrack::Right => 2,
+    pub fn x_position(&self, lane_layout: &LaneLayout) -> f32 {
+        lane_layout.x_position(self.0)
+    }
+}
+
+/// Lane the player last switched away from, used to bounce back after a side bump
+#[derive(Component)]
+pub struct LastLaneChange {
+    pub from: CurrentTrack,
+}
+
+/// In-flight lane change; `CurrentTrack` already holds the destination lane
+#[derive(Component)]
+pub struct LaneSwitch {
+    pub from: CurrentTrack,
+    pub start_x: f32,
+    pub elapsed: f32,
+    pub duration: f32,
+}
+
+impl LaneSwitch {
+    pub fn new(
+        from: CurrentTrack,
+        start_x: f32,
+        target_x: f32,
+        lane_layout: &LaneLayout,
+        game_config: &GameConfig,
+    ) -> Self {
+        // Reversing part-way covers less ground, so it takes proportionally less time
+        let lanes_to_cover = (target_x - start_x).abs() / lane_layout.spacing;
+        Self {
+            from,
+            start_x,
+            elapsed: 0.0,
+            duration: game_config.lane_switch_duration * lanes_to_cover.max(0.1),
         }
     }
 }
 
+/// Visual pivot for the body, rolled while changing lanes without touching the collider
+#[derive(Component)]
+pub struct PlayerModel;
+
+/// Whether the player is standing on something, and when they last were (for coyote time)
+#[derive(Component)]
+pub struct GroundContact {
+    pub grounded: bool,
+    pub last_grounded_at: f32,
+    pub platform_velocity: f32, // Z velocity of the train being stood on, if any
+    pub on_train: bool,         // Standing on a train roof or ramp
+}
+
+impl GroundContact {
+    pub fn can_jump(&self, now: f32, coyote_time: f32) -> bool {
+        self.grounded || now - self.last_grounded_at <= coyote_time
+    }
+}
+
+// Downward probe: a sphere slightly narrower than the capsule, reaching just past its feet
+const GROUND_PROBE_RADIUS: f32 = 0.7;
+const GROUND_PROBE_DISTANCE: f32 = 0.4;
+
+/// Half-width of the player's body used by the lateral hit checks
+pub const PLAYER_HALF_WIDTH: f32 = 0.3;
+
 #[derive(Component)]
 pub struct Velocity {
     pub forward: f32,
@@ -30,14 +86,63 @@ pub struct Velocity {
 pub enum AnimationState {
     Running,
     Sliding,
-    Jumping,
+    Jumping, // Rising after a jump
+    Falling, // Descending, from a jump or off an edge
+    Rolling, // Fast-fall slam from sliding in mid-air, turns into a slide on landing
+    Landing, // Brief touchdown before running again
 }
 
+/// Runs while sliding; the timer is the longest a held slide can last
 #[derive(Component)]
 pub struct SlideTimer {
     pub timer: Timer,
 }
 
+/// The player's collider is currently the short sliding one
+#[derive(Component)]
+pub struct Crouched;
+
+// Collider half-heights; the standing capsule is radius 0.8 plus a 0.4 long segment
+pub const STANDING_HALF_HEIGHT: f32 = 1.0;
+pub const CROUCHED_HALF_HEIGHT: f32 = 0.5;
+
+pub fn standing_collider() -> Collider {
+    Collider::capsule(0.8, 0.4)
+}
+
+pub fn crouched_collider() -> Collider {
+    Collider::capsule(CROUCHED_HALF_HEIGHT, 0.0)
+}
+
+pub fn half_height(crouched: bool) -> f32 {
+    if crouched {
+        CROUCHED_HALF_HEIGHT
+    } else {
+        STANDING_HALF_HEIGHT
+    }
+}
+
+/// Swaps in the short collider, lowering the body so the feet stay on the ground
+pub fn crouch(commands: &mut Commands, entity: Entity, transform: &mut Transform) {
+    transform.translation.y -= STANDING_HALF_HEIGHT - CROUCHED_HALF_HEIGHT;
+    commands
+        .entity(entity)
+        .insert((Crouched, crouched_collider()));
+}
+
+pub fn stand_up(commands: &mut Commands, entity: Entity, transform: &mut Transform) {
+    transform.translation.y += STANDING_HALF_HEIGHT - CROUCHED_HALF_HEIGHT;
+    commands
+        .entity(entity)
+        .remove::<Crouched>()
+        .insert(standing_collider());
+}
+
+#[derive(Component)]
+pub struct LandingTimer {
+    pub timer: Timer,
+}
+
 // Limb marker components for animation
 #[derive(Component)]
 pub struct LeftArm;
@@ -56,8 +161,10 @@ pub fn spawn_player(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     game_config: Res<crate::resources::game_config::GameConfig>,
+    lane_layout: Res<LaneLayout>,
 ) {
-    let x_position = 0.0;
+    let start_lane = CurrentTrack(lane_layout.middle());
+    let x_position = start_lane.x_position(&lane_layout);
 
     // Colors
     let skin_color = Color::srgb(0.9, 0.75, 0.6);
@@ -78,64 +185,114 @@ pub fn spawn_player(
 
     let mut player_entity = commands.spawn((
         Player,
-        CurrentTrack::Middle,
+        start_lane,
+        LastLaneChange { from: start_lane },
         Velocity {
             forward: game_config.base_speed,
         },
         AnimationState::Running,
+        OnPath,
         Transform::from_xyz(x_position, 1.5, 0.0),
+        Visibility::default(),
         RigidBody::Dynamic,
-        Collider::capsule(0.8, 0.4),
+        standing_collider(),
         LockedAxes::ROTATION_LOCKED,
         LinearVelocity::ZERO,
         GravityScale(2.5),
+        GroundContact {
+            grounded: false,
+            last_grounded_at: f32::NEG_INFINITY,
+            platform_velocity: 0.0,
+            on_train: false,
+        },
+        ShapeCaster::new(
+            Collider::sphere(GROUND_PROBE_RADIUS),
+            Vec3::ZERO,
+            Quat::IDENTITY,
+            Dir3::NEG_Y,
+        )
+        .with_max_distance(GROUND_PROBE_DISTANCE),
     ));
 
     player_entity.with_children(|parent| {
-        // Head
-        parent.spawn((
-            Mesh3d(head_mesh.clone()),
-            MeshMaterial3d(skin_mat.clone()),
-            Transform::from_xyz(0.0, 0.7, 0.0),
-        ));
-
-        // Torso
-        parent.spawn((
-            Mesh3d(torso_mesh.clone()),
-            MeshMaterial3d(shirt_mat.clone()),
-            Transform::from_xyz(0.0, 0.25, 0.0),
-        ));
-
-        // Left Arm (pivot at shoulder)
-        parent.spawn((
-            LeftArm,
-            Mesh3d(arm_mesh.clone()),
-            MeshMaterial3d(shirt_mat.clone()),
-            Transform::from_xyz(-0.32, 0.3, 0.0).with_rotation(Quat::from_rotation_z(0.15)),
-        ));
-
-        // Right Arm (pivot at shoulder)
-        parent.spawn((
-            RightArm,
-            Mesh3d(arm_mesh.clone()),
-            MeshMaterial3d(shirt_mat.clone()),
-            Transform::from_xyz(0.32, 0.3, 0.0).with_rotation(Quat::from_rotation_z(-0.15)),
-        ));
-
-        // Left Leg (pivot at hip)
-        parent.spawn((
-            LeftLeg,
-            Mesh3d(leg_mesh.clone()),
-            MeshMaterial3d(pants_mat.clone()),
-            Transform::from_xyz(-0.14, -0.22, 0.0),
-        ));
-
-        // Right Leg (pivot at hip)
-        parent.spawn((
-            RightLeg,
-            Mesh3d(leg_mesh.clone()),
-            MeshMaterial3d(pants_mat.clone()),
-            Transform::from_xyz(0.14, -0.22, 0.0),
-        ));
+        parent
+            .spawn((PlayerModel, Transform::default(), Visibility::default()))
+            .with_children(|parent| {
+                // Head
+                parent.spawn((
+                    Mesh3d(head_mesh.clone()),
+                    MeshMaterial3d(skin_mat.clone()),
+                    Transform::from_xyz(0.0, 0.7, 0.0),
+                ));
+
+                // Torso
+                parent.spawn((
+                    Mesh3d(torso_mesh.clone()),
+                    MeshMaterial3d(shirt_mat.clone()),
+                    Transform::from_xyz(0.0, 0.25, 0.0),
+                ));
+
+                // Left Arm (pivot at shoulder)
+                parent.spawn((
+                    LeftArm,
+                    Mesh3d(arm_mesh.clone()),
+                    MeshMaterial3d(shirt_mat.clone()),
+                    Transform::from_xyz(-0.32, 0.3, 0.0).with_rotation(Quat::from_rotation_z(0.15)),
+                ));
+
+                // Right Arm (pivot at shoulder)
+                parent.spawn((
+                    RightArm,
+                    Mesh3d(arm_mesh.clone()),
+                    MeshMaterial3d(shirt_mat.clone()),
+                    Transform::from_xyz(0.32, 0.3, 0.0).with_rotation(Quat::from_rotation_z(-0.15)),
+                ));
+
+                // Left Leg (pivot at hip)
+                parent.spawn((
+                    LeftLeg,
+                    Mesh3d(leg_mesh.clone()),
+                    MeshMaterial3d(pants_mat.clone()),
+                    Transform::from_xyz(-0.14, -0.22, 0.0),
+                ));
+
+                // Right Leg (pivot at hip)
+                parent.spawn((
+                    RightLeg,
+                    Mesh3d(leg_mesh.clone()),
+                    MeshMaterial3d(pants_mat.clone()),
+                    Transform::from_xyz(0.14, -0.22, 0.0),
+                ));
+            });
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn left_surface_at(time: f32) -> GroundContact {
+        GroundContact {
+            grounded: false,
+            last_grounded_at: time,
+            platform_velocity: 0.0,
+            on_train: false,
+        }
+    }
+
+    #[test]
+    fn can_jump_within_coyote_time_of_leaving_a_surface() {
+        let ground_contact = left_surface_at(2.0);
+        assert!(ground_contact.can_jump(2.05, 0.1));
+        assert!(!ground_contact.can_jump(2.15, 0.1));
+    }
+
+    #[test]
+    fn can_always_jump_while_grounded() {
+        let ground_contact = GroundContact {
+            grounded: true,
+            ..left_surface_at(0.0)
+        };
+        assert!(ground_contact.can_jump(100.0, 0.1));
+    }
+}
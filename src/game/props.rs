@@ -1,71 +1,359 @@
+use crate::game::day_cycle::NightLight;
+use crate::game::path::OnPath;
+use crate::resources::draw_horizon::DrawHorizon;
+use crate::resources::lane_layout::LaneLayout;
+use crate::resources::theme::{Theme, Themes};
+use crate::resources::track_path::TrackPath;
 use bevy::prelude::*;
 
-#[derive(Component, Clone, Copy)]
+#[derive(Component, Clone, Copy, PartialEq)]
 pub enum PropType {
     Building,
     Sign,
-    Light,
+    LampPost,
+    Pillar,
+    Fence,
+}
+
+impl PropType {
+    /// Buildings stand back along the street; everything else lines the track edge
+    fn is_street(self) -> bool {
+        self == PropType::Building
+    }
+}
+
+/// Where the streamer decided to put the next prop in a row
+struct PropSpot {
+    side: i8,
+    start_z: f32,
+    seed: u32,
 }
 
 #[derive(Component)]
 pub struct Prop {
     pub prop_type: PropType,
+    pub side: i8,   // -1 on the negative x side of the track, 1 on the positive side
+    pub end_z: f32, // Far end of the prop; the next one in its row starts past this
 }
 
-pub fn generate_props(
+// Gap between the outermost lane edge and the trackside row
+const TRACKSIDE_MARGIN: f32 = 0.8;
+// Gap between the outermost lane edge and the building fronts
+const STREET_MARGIN: f32 = 4.0;
+const FLOOR_HEIGHT: f32 = 3.5;
+
+// Simple pseudo-random for prop placement
+fn prop_random(seed: u32) -> u32 {
+    let mut x = seed;
+    x ^= x << 13;
+    x ^= x >> 17;
+    x ^= x << 5;
+    x
+}
+
+/// Keeps both sides of the track lined with buildings and trackside props as the run goes on.
+///
+/// Every row carries on from its furthest prop, and each placement is seeded from the
+/// run's path seed, the side and the distance, so a run always builds the same city.
+/// What gets built, and in which colours, comes from the theme at that distance.
+pub fn stream_props(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    game_config: Res<crate::resources::game_config::GameConfig>,
+    prop_query: Query<&Prop>,
+    camera_query: Query<&Transform, With<Camera3d>>,
+    lane_layout: Res<LaneLayout>,
+    track_path: Res<TrackPath>,
+    themes: Res<Themes>,
+    draw_horizon: Res<DrawHorizon>,
 ) {
-    let track_spacing = game_config.track_spacing;
-    let prop_types = [PropType::Building, PropType::Sign, PropType::Light];
-
-    for i in 0..10 {
-        let prop_type = prop_types[i % 3];
-        let x_offset = if i % 2 == 0 {
-            -track_spacing * 1.5
-        } else {
-            track_spacing * 1.5
-        };
-        let z_position = (i as f32) * 5.0;
-
-        let (mesh, color) = match prop_type {
-            PropType::Building => (
-                meshes.add(Cuboid::new(1.0, 2.0, 1.0)),
-                Color::srgb(0.5, 0.5, 0.6),
-            ),
-            PropType::Sign => (
-                meshes.add(Cuboid::new(0.5, 1.0, 0.1)),
-                Color::srgb(0.8, 0.2, 0.2),
-            ),
-            PropType::Light => (
-                meshes.add(Cylinder::new(0.2, 1.5)),
-                Color::srgb(0.9, 0.9, 0.3),
-            ),
-        };
-
-        commands.spawn((
-            Prop { prop_type },
-            Mesh3d(mesh),
-            MeshMaterial3d(materials.add(color)),
-            Transform::from_xyz(x_offset, 1.0, z_position),
-        ));
+    let Ok(camera_transform) = camera_query.single() else {
+        return;
+    };
+    let camera_z = camera_transform.translation.z;
+    let horizon = camera_z + draw_horizon.distance;
+
+    for side in [-1i8, 1] {
+        for street in [false, true] {
+            // Carry on from the furthest prop in this row, or start just behind the camera
+            let mut next_z = prop_query
+                .iter()
+                .filter(|prop| prop.side == side && prop.prop_type.is_street() == street)
+                .map(|prop| prop.end_z)
+                .fold(camera_z - 20.0, f32::max);
+
+            let row_key = match (side, street) {
+                (-1, false) => 0x1000_0000,
+                (-1, true) => 0x2000_0000,
+                (_, false) => 0x3000_0000,
+                (_, true) => 0x4000_0000,
+            };
+            while next_z < horizon {
+                let theme = themes.at(next_z);
+                let spot = PropSpot {
+                    side,
+                    start_z: next_z,
+                    seed: prop_random(track_path.seed() ^ row_key ^ (next_z * 7.7) as u32),
+                };
+                next_z = if street {
+                    spawn_building(
+                        &mut commands,
+                        &mut meshes,
+                        &mut materials,
+                        spot,
+                        &lane_layout,
+                        &theme,
+                    )
+                } else {
+                    spawn_trackside_prop(
+                        &mut commands,
+                        &mut meshes,
+                        &mut materials,
+                        spot,
+                        &lane_layout,
+                        &theme,
+                    )
+                };
+            }
+        }
     }
 }
 
+/// Spawns a building with a grid of windows facing the track; returns where the next one may start
+fn spawn_building(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    spot: PropSpot,
+    lane_layout: &LaneLayout,
+    theme: &Theme,
+) -> f32 {
+    let PropSpot {
+        side,
+        start_z,
+        seed,
+    } = spot;
+    let side_sign = side as f32;
+    let width = 6.0 + (seed % 5) as f32;
+    let depth = 8.0 + (seed / 5 % 9) as f32;
+    let (fewest_floors, most_floors) = theme.floors;
+    let floors = fewest_floors + seed / 45 % (most_floors - fewest_floors + 1);
+    let height = floors as f32 * FLOOR_HEIGHT;
+    let gap = 1.0 + (seed / 315 % 4) as f32;
+    let wall_color = theme.buildings[(seed / 1260) as usize % theme.buildings.len()];
+
+    let x = side_sign * (lane_layout.half_width() + STREET_MARGIN + width / 2.0);
+    let center_z = start_z + depth / 2.0;
+    let end_z = start_z + depth;
+
+    // Windows all look alike by day; after dark some of them light up
+    let window_mesh = meshes.add(Cuboid::new(0.05, 1.4, 1.6));
+    let dark_window = materials.add(Color::srgb(0.15, 0.2, 0.28));
+    let lit_window = materials.add(Color::srgb(0.15, 0.2, 0.28));
+    let window_glow = NightLight {
+        emissive: theme.window_light.to_linear() * 1.2,
+    };
+
+    commands
+        .spawn((
+            Prop {
+                prop_type: PropType::Building,
+                side,
+                end_z,
+            },
+            OnPath,
+            Mesh3d(meshes.add(Cuboid::new(width, height, depth))),
+            MeshMaterial3d(materials.add(wall_color)),
+            Transform::from_xyz(x, height / 2.0, center_z),
+        ))
+        .with_children(|parent| {
+            // Window grid on the face towards the track
+            let face_x = -side_sign * (width / 2.0 + 0.03);
+            let columns = (depth / 3.0).floor().max(1.0) as u32;
+            let column_spacing = depth / columns as f32;
+            for floor in 0..floors {
+                let y = -height / 2.0 + (floor as f32 + 0.55) * FLOOR_HEIGHT;
+                for column in 0..columns {
+                    let z = -depth / 2.0 + (column as f32 + 0.5) * column_spacing;
+                    let window = (
+                        Mesh3d(window_mesh.clone()),
+                        Transform::from_xyz(face_x, y, z),
+                    );
+                    if prop_random(seed ^ (floor * 16 + column + 1)) % 3 == 0 {
+                        parent.spawn((window, MeshMaterial3d(lit_window.clone()), window_glow));
+                    } else {
+                        parent.spawn((window, MeshMaterial3d(dark_window.clone())));
+                    }
+                }
+            }
+        });
+
+    end_z + gap
+}
+
+/// Picks a trackside prop by the theme's weights
+fn pick_trackside_prop(theme: &Theme, seed: u32) -> PropType {
+    let weights = theme.props;
+    let total = weights.lamp_post + weights.pillar + weights.fence + weights.sign;
+    let roll = seed % total.max(1);
+    if roll < weights.lamp_post {
+        PropType::LampPost
+    } else if roll < weights.lamp_post + weights.pillar {
+        PropType::Pillar
+    } else if roll < weights.lamp_post + weights.pillar + weights.fence {
+        PropType::Fence
+    } else {
+        PropType::Sign
+    }
+}
+
+/// Spawns a lamp post, platform pillar, fence panel or sign by the track; returns where
+/// the next one may start
+fn spawn_trackside_prop(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    spot: PropSpot,
+    lane_layout: &LaneLayout,
+    theme: &Theme,
+) -> f32 {
+    let PropSpot {
+        side,
+        start_z,
+        seed,
+    } = spot;
+    let side_sign = side as f32;
+    let prop_type = pick_trackside_prop(theme, seed);
+    // Length along the track, and the space left before the next prop
+    let (length, gap) = match prop_type {
+        PropType::Fence => (6.0, 0.0),
+        _ => (0.4, 6.0 + (seed / 10 % 8) as f32),
+    };
+    let x = side_sign * (lane_layout.half_width() + TRACKSIDE_MARGIN);
+    let center_z = start_z + length / 2.0;
+    let end_z = start_z + length;
+    // Local x pointing from the prop towards the track
+    let inward = -side_sign;
+
+    let mut prop = commands.spawn((
+        Prop {
+            prop_type,
+            side,
+            end_z,
+        },
+        OnPath,
+        Transform::from_xyz(x, 0.0, center_z),
+        Visibility::default(),
+    ));
+
+    match prop_type {
+        PropType::LampPost => {
+            let steel = materials.add(Color::srgb(0.25, 0.27, 0.3));
+            let pole_mesh = meshes.add(Cylinder::new(0.08, 4.0));
+            let arm_mesh = meshes.add(Cuboid::new(0.8, 0.06, 0.06));
+            let lamp_mesh = meshes.add(Sphere::new(0.15));
+            let lamp_material = materials.add(theme.lamp_light);
+            prop.with_children(|parent| {
+                parent.spawn((
+                    Mesh3d(pole_mesh),
+                    MeshMaterial3d(steel.clone()),
+                    Transform::from_xyz(0.0, 2.0, 0.0),
+                ));
+                parent.spawn((
+                    Mesh3d(arm_mesh),
+                    MeshMaterial3d(steel),
+                    Transform::from_xyz(inward * 0.4, 3.95, 0.0),
+                ));
+                parent.spawn((
+                    NightLight {
+                        emissive: theme.lamp_light.to_linear() * 4.0,
+                    },
+                    Mesh3d(lamp_mesh),
+                    MeshMaterial3d(lamp_material),
+                    Transform::from_xyz(inward * 0.8, 3.85, 0.0),
+                ));
+            });
+        }
+        PropType::Pillar => {
+            let concrete = materials.add(theme.tunnel);
+            let column_mesh = meshes.add(Cylinder::new(0.3, 5.0));
+            let cap_mesh = meshes.add(Cuboid::new(0.9, 0.3, 0.9));
+            prop.with_children(|parent| {
+                parent.spawn((
+                    Mesh3d(column_mesh),
+                    MeshMaterial3d(concrete.clone()),
+                    Transform::from_xyz(0.0, 2.5, 0.0),
+                ));
+                parent.spawn((
+                    Mesh3d(cap_mesh),
+                    MeshMaterial3d(concrete),
+                    Transform::from_xyz(0.0, 5.15, 0.0),
+                ));
+            });
+        }
+        PropType::Fence => {
+            let paint = materials.add(theme.fence);
+            let post_mesh = meshes.add(Cuboid::new(0.1, 1.2, 0.1));
+            let rail_mesh = meshes.add(Cuboid::new(0.05, 0.08, length));
+            prop.with_children(|parent| {
+                for end in [-1.0, 1.0] {
+                    parent.spawn((
+                        Mesh3d(post_mesh.clone()),
+                        MeshMaterial3d(paint.clone()),
+                        Transform::from_xyz(0.0, 0.6, end * (length / 2.0 - 0.05)),
+                    ));
+                }
+                for y in [0.5, 1.0] {
+                    parent.spawn((
+                        Mesh3d(rail_mesh.clone()),
+                        MeshMaterial3d(paint.clone()),
+                        Transform::from_xyz(0.0, y, 0.0),
+                    ));
+                }
+            });
+        }
+        _ => {
+            // Sign board on a post
+            let board_color = theme.signs[(seed / 80) as usize % theme.signs.len()];
+            let post_mesh = meshes.add(Cylinder::new(0.06, 2.5));
+            let board_mesh = meshes.add(Cuboid::new(0.1, 1.0, 1.6));
+            prop.with_children(|parent| {
+                parent.spawn((
+                    Mesh3d(post_mesh),
+                    MeshMaterial3d(materials.add(Color::srgb(0.3, 0.3, 0.3))),
+                    Transform::from_xyz(0.0, 1.25, 0.0),
+                ));
+                parent.spawn((
+                    Mesh3d(board_mesh),
+                    MeshMaterial3d(materials.add(board_color)),
+                    Transform::from_xyz(0.0, 2.4, 0.0),
+                ));
+            });
+        }
+    }
+
+    end_z + gap
+}
+
 pub fn recycle_props(
     mut commands: Commands,
-    prop_query: Query<(Entity, &Transform), With<Prop>>,
+    prop_query: Query<(Entity, &Prop)>,
     camera_query: Query<&Transform, (With<Camera3d>, Without<Prop>)>,
 ) {
     if let Ok(camera_transform) = camera_query.single() {
         let camera_z = camera_transform.translation.z;
 
-        for (entity, transform) in prop_query.iter() {
-            if transform.translation.z < camera_z - 20.0 {
+        // Long buildings stay until their far end is out of view
+        for (entity, prop) in prop_query.iter() {
+            if prop.end_z < camera_z - 20.0 {
                 commands.entity(entity).despawn();
             }
         }
     }
 }
+
+pub fn despawn_props(mut commands: Commands, prop_query: Query<Entity, With<Prop>>) {
+    for entity in prop_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,121 @@
+use crate::game::barricade::Barricade;
+use crate::game::chaser::Chaser;
+use crate::game::player::Player;
+use crate::game::train::Train;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+// Seconds of play kept for the crash replay
+const REPLAY_LENGTH: f32 = 3.0;
+// Only obstacles this far ahead of or behind the player are recorded
+const REPLAY_RANGE: f32 = 40.0;
+// Pause on the moment of the crash before the replay loops
+const REPLAY_HOLD: f32 = 1.5;
+const ORBIT_RADIUS: f32 = 8.0;
+const ORBIT_HEIGHT: f32 = 4.0;
+const ORBIT_SPEED: f32 = 0.4; // Radians per second
+
+struct ReplayFrame {
+    time: f32,
+    player: Transform,
+    obstacles: Vec<(Entity, Transform)>,
+}
+
+/// Rolling record of the last few seconds of a run, replayed on the game over screen
+#[derive(Resource, Default)]
+pub struct ReplayBuffer {
+    frames: VecDeque<ReplayFrame>,
+    playback: f32, // Seconds since the replay started
+}
+
+type ReplayedObstacle = Or<(With<Train>, With<Barricade>, With<Chaser>)>;
+
+pub fn record_replay(
+    mut replay: ResMut<ReplayBuffer>,
+    time: Res<Time>,
+    player_query: Query<&Transform, With<Player>>,
+    obstacle_query: Query<(Entity, &Transform), (ReplayedObstacle, Without<Player>)>,
+) {
+    let Ok(player_transform) = player_query.single() else {
+        return;
+    };
+    let now = time.elapsed_secs();
+    let player_z = player_transform.translation.z;
+
+    let obstacles = obstacle_query
+        .iter()
+        .filter(|(_, transform)| (transform.translation.z - player_z).abs() < REPLAY_RANGE)
+        .map(|(entity, transform)| (entity, *transform))
+        .collect();
+    replay.frames.push_back(ReplayFrame {
+        time: now,
+        player: *player_transform,
+        obstacles,
+    });
+
+    while replay
+        .frames
+        .front()
+        .is_some_and(|frame| frame.time < now - REPLAY_LENGTH)
+    {
+        replay.frames.pop_front();
+    }
+}
+
+pub fn start_replay(mut replay: ResMut<ReplayBuffer>) {
+    replay.playback = 0.0;
+}
+
+/// Loops the recorded crash on the game over screen, circling the camera around the player
+pub fn play_crash_replay(
+    mut replay: ResMut<ReplayBuffer>,
+    time: Res<Time>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+    mut obstacle_query: Query<
+        &mut Transform,
+        (ReplayedObstacle, Without<Player>, Without<Camera3d>),
+    >,
+    mut camera_query: Query<&mut Transform, (With<Camera3d>, Without<Player>)>,
+) {
+    replay.playback += time.delta_secs();
+    let playback = replay.playback;
+    let (Some(first), Some(last)) = (replay.frames.front(), replay.frames.back()) else {
+        return;
+    };
+
+    // Play the recording through, then hold on the crash before starting over
+    let length = last.time - first.time;
+    let replay_time = first.time + (playback % (length + REPLAY_HOLD)).min(length);
+    let frame = replay
+        .frames
+        .iter()
+        .find(|frame| frame.time >= replay_time)
+        .unwrap_or(last);
+
+    if let Ok(mut player_transform) = player_query.single_mut() {
+        *player_transform = frame.player;
+    }
+    // Obstacles recycled since they were recorded are simply left out
+    for (entity, transform) in &frame.obstacles {
+        if let Ok(mut obstacle_transform) = obstacle_query.get_mut(*entity) {
+            *obstacle_transform = *transform;
+        }
+    }
+
+    if let Ok(mut camera_transform) = camera_query.single_mut() {
+        // Start from behind the player, as during the run
+        let angle = playback * ORBIT_SPEED;
+        let focus = frame.player.translation;
+        let offset = Vec3::new(
+            angle.sin() * ORBIT_RADIUS,
+            ORBIT_HEIGHT,
+            -angle.cos() * ORBIT_RADIUS,
+        );
+        *camera_transform =
+            Transform::from_translation(focus + offset).looking_at(focus + Vec3::Y, Vec3::Y);
+    }
+}
+
+pub fn clear_replay(mut replay: ResMut<ReplayBuffer>) {
+    replay.frames.clear();
+}
@@ -0,0 +1,180 @@
+use crate::game::action_map::{
+    Action, ActionMap, ActionState, BINDABLE_BUTTONS, BINDABLE_KEYS, Binding,
+};
+use crate::game::audio::VolumeCategory;
+use crate::resources::profile::PlayerProfile;
+use bevy::prelude::*;
+
+/// Rebinding and volume screen opened from the game over screen
+#[derive(Resource, Default)]
+pub struct SettingsMenu {
+    pub open: bool,
+    selected: usize, // Index into `Action::ALL`, then `VolumeCategory::ALL` after it
+    listening: bool, // Waiting for the key or button to bind to the selected action
+}
+
+#[derive(Component)]
+pub struct SettingsText;
+
+/// Run condition keeping game over input from firing underneath the settings screen
+pub fn settings_closed(settings_menu: Res<SettingsMenu>) -> bool {
+    !settings_menu.open
+}
+
+pub fn handle_settings_menu(
+    mut commands: Commands,
+    mut settings_menu: ResMut<SettingsMenu>,
+    mut action_map: ResMut<ActionMap>,
+    mut profile: ResMut<PlayerProfile>,
+    action_state: Res<ActionState>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    text_query: Query<Entity, With<SettingsText>>,
+) {
+    if !settings_menu.open {
+        if action_state.just_pressed(Action::Settings) {
+            settings_menu.open = true;
+            settings_menu.listening = false;
+            spawn_settings_ui(&mut commands);
+        }
+        return;
+    }
+
+    if settings_menu.listening {
+        if action_state.just_pressed(Action::Back) {
+            settings_menu.listening = false;
+            return;
+        }
+
+        let key = keyboard_input
+            .get_just_pressed()
+            .find(|key| BINDABLE_KEYS.contains(key))
+            .map(|key| Binding::Key(*key));
+        let button = gamepads.iter().find_map(|gamepad| {
+            gamepad
+                .get_just_pressed()
+                .find(|button| BINDABLE_BUTTONS.contains(button))
+                .map(|button| Binding::Button(*button))
+        });
+
+        if let Some(binding) = key.or(button) {
+            action_map.rebind(Action::ALL[settings_menu.selected], binding);
+            action_map.save_to_profile(&mut profile);
+            settings_menu.listening = false;
+        }
+        return;
+    }
+
+    let row_count = Action::ALL.len() + VolumeCategory::ALL.len();
+    if action_state.just_pressed(Action::MenuUp) {
+        settings_menu.selected = (settings_menu.selected + row_count - 1) % row_count;
+    }
+    if action_state.just_pressed(Action::MenuDown) {
+        settings_menu.selected = (settings_menu.selected + 1) % row_count;
+    }
+
+    match settings_menu.selected.checked_sub(Action::ALL.len()) {
+        None => {
+            if action_state.just_pressed(Action::Confirm) {
+                settings_menu.listening = true;
+            }
+        }
+        Some(volume_index) => {
+            let steps = action_state.just_pressed(Action::MoveRight) as i32
+                - action_state.just_pressed(Action::MoveLeft) as i32;
+            if steps != 0 {
+                VolumeCategory::ALL[volume_index].step(&mut profile, steps);
+                profile.save();
+            }
+        }
+    }
+    if action_state.just_pressed(Action::Back) || action_state.just_pressed(Action::Settings) {
+        settings_menu.open = false;
+        for entity in text_query.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn spawn_settings_ui(commands: &mut Commands) {
+    commands.spawn((
+        SettingsText,
+        Text::new(""),
+        TextFont {
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(60.0),
+            left: Val::Px(20.0),
+            padding: UiRect::all(Val::Px(12.0)),
+            ..default()
+        },
+    ));
+}
+
+pub fn update_settings_ui(
+    settings_menu: Res<SettingsMenu>,
+    action_map: Res<ActionMap>,
+    profile: Res<PlayerProfile>,
+    mut query: Query<&mut Text, With<SettingsText>>,
+) {
+    for mut text in query.iter_mut() {
+        let mut lines = vec![format!(
+            "SETTINGS - {} / {} to choose, {} to rebind, {} / {} for volume, {} to close",
+            action_map.describe(Action::MenuUp),
+            action_map.describe(Action::MenuDown),
+            action_map.describe(Action::Confirm),
+            action_map.describe(Action::MoveLeft),
+            action_map.describe(Action::MoveRight),
+            action_map.describe(Action::Back)
+        )];
+
+        for (index, action) in Action::ALL.iter().enumerate() {
+            let marker = if index == settings_menu.selected {
+                ">"
+            } else {
+                " "
+            };
+            let bindings = if index == settings_menu.selected && settings_menu.listening {
+                "press a key or button...".to_string()
+            } else {
+                action_map
+                    .bindings(*action)
+                    .iter()
+                    .map(Binding::label)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            lines.push(format!("{marker} {}: {bindings}", action.label()));
+        }
+
+        for (index, category) in VolumeCategory::ALL.iter().enumerate() {
+            let marker = if Action::ALL.len() + index == settings_menu.selected {
+                ">"
+            } else {
+                " "
+            };
+            let percent = (category.level(&profile) * 100.0).round();
+            lines.push(format!("{marker} {}: {percent}%", category.label()));
+        }
+
+        **text = lines.join("\n");
+    }
+}
+
+/// Makes sure the screen never carries over into the next run
+pub fn close_settings_menu(
+    mut commands: Commands,
+    mut settings_menu: ResMut<SettingsMenu>,
+    text_query: Query<Entity, With<SettingsText>>,
+) {
+    settings_menu.open = false;
+    settings_menu.listening = false;
+    for entity in text_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
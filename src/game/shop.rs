@@ -0,0 +1,65 @@
+use crate::game::action_map::{Action, ActionMap, ActionState};
+use crate::game::game_state::GameOverText;
+use crate::resources::game_config::GameConfig;
+use crate::resources::profile::PlayerProfile;
+use crate::resources::score::Score;
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct ShopText;
+
+/// Moves the coins collected this run into the profile bank
+pub fn bank_run_coins(score: Res<Score>, mut profile: ResMut<PlayerProfile>) {
+    profile.coins += score.coins;
+    profile.save();
+}
+
+pub fn show_shop_ui(mut commands: Commands) {
+    // Tagged as game over UI so the restart handler clears it with the rest
+    commands.spawn((
+        ShopText,
+        GameOverText,
+        Text::new(""),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.1, 0.8, 0.9)),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(20.0),
+            left: Val::Px(20.0),
+            ..default()
+        },
+    ));
+}
+
+pub fn handle_shop_purchase(
+    action_state: Res<ActionState>,
+    game_config: Res<GameConfig>,
+    mut profile: ResMut<PlayerProfile>,
+) {
+    if action_state.just_pressed(Action::BuyHoverboard)
+        && profile.try_spend(game_config.hoverboard_price)
+    {
+        profile.hoverboards += 1;
+        profile.save();
+    }
+}
+
+pub fn update_shop_ui(
+    profile: Res<PlayerProfile>,
+    game_config: Res<GameConfig>,
+    action_map: Res<ActionMap>,
+    mut query: Query<&mut Text, With<ShopText>>,
+) {
+    for mut text in query.iter_mut() {
+        **text = format!(
+            "SHOP - Bank: {} coins | Hoverboards owned: {}\nPress {} to buy a hoverboard ({} coins)",
+            profile.coins,
+            profile.hoverboards,
+            action_map.describe(Action::BuyHoverboard),
+            game_config.hoverboard_price
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use crate::game::path::OnPath;
+use crate::game::player::{Player, Velocity};
+use crate::game::train::{Train, TrainType};
+use crate::resources::game_config::GameConfig;
+use bevy::prelude::*;
+
+/// Sent when a train starts its approach warning, for the horn sound
+#[derive(Message)]
+pub struct TrainHorn {
+    pub train: Entity,
+    pub track_index: u8,
+    pub express: bool,
+}
+
+/// Marks a train whose approach has already been announced
+#[derive(Component)]
+pub struct ApproachWarned;
+
+/// Flashing lamp on the lane of an incoming train, kept just ahead of the player and
+/// following the train across if it changes lanes
+#[derive(Component)]
+pub struct LaneSignal {
+    pub train: Entity,
+    pub flash: Timer,
+}
+
+// How far ahead of the player the signal sits
+const SIGNAL_LEAD: f32 = 10.0;
+const SIGNAL_FLASH_INTERVAL: f32 = 0.2;
+
+pub fn warn_approaching_trains(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    train_query: Query<(Entity, &Transform, &Train), Without<ApproachWarned>>,
+    player_query: Query<(&Transform, &Velocity), (With<Player>, Without<Train>)>,
+    game_config: Res<GameConfig>,
+    mut horns: MessageWriter<TrainHorn>,
+) {
+    let Ok((player_transform, velocity)) = player_query.single() else {
+        return;
+    };
+    let player_z = player_transform.translation.z;
+
+    for (entity, transform, train) in train_query.iter() {
+        if !train.train_type.is_moving() {
+            continue;
+        }
+
+        let distance = transform.translation.z - train.length / 2.0 - player_z;
+        if distance <= 0.0 {
+            continue;
+        }
+        let closing_speed = velocity.forward + train.speed;
+        if distance / closing_speed > game_config.train_warning_time {
+            continue;
+        }
+
+        commands.entity(entity).insert(ApproachWarned);
+        horns.write(TrainHorn {
+            train: entity,
+            track_index: train.track_index,
+            express: train.train_type == TrainType::Express,
+        });
+
+        commands.spawn((
+            LaneSignal {
+                train: entity,
+                flash: Timer::from_seconds(SIGNAL_FLASH_INTERVAL, TimerMode::Repeating),
+            },
+            OnPath,
+            Mesh3d(meshes.add(Cuboid::new(1.2, 0.05, 0.6))),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: Color::srgb(1.0, 0.1, 0.05),
+                emissive: LinearRgba::rgb(6.0, 0.4, 0.2),
+                ..default()
+            })),
+            Transform::from_xyz(transform.translation.x, 0.03, player_z + SIGNAL_LEAD),
+            Visibility::Visible,
+        ));
+    }
+}
+
+/// Blinks signals and removes them once their train has passed the player
+pub fn flash_lane_signals(
+    mut commands: Commands,
+    mut signal_query: Query<(Entity, &mut LaneSignal, &mut Transform, &mut Visibility)>,
+    train_query: Query<(&Transform, &Train), Without<LaneSignal>>,
+    player_query: Query<&Transform, (With<Player>, Without<LaneSignal>, Without<Train>)>,
+    time: Res<Time>,
+) {
+    let Ok(player_transform) = player_query.single() else {
+        return;
+    };
+    let player_z = player_transform.translation.z;
+
+    for (entity, mut signal, mut transform, mut visibility) in signal_query.iter_mut() {
+        let Some(train_x) = train_query
+            .get(signal.train)
+            .ok()
+            .filter(|(train_transform, train)| {
+                train_transform.translation.z - train.length / 2.0 >= player_z
+            })
+            .map(|(train_transform, _)| train_transform.translation.x)
+        else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        // A train crossing to another lane takes its warning with it
+        transform.translation.x = train_x;
+        transform.translation.z = player_z + SIGNAL_LEAD;
+        signal.flash.tick(time.delta());
+        if signal.flash.just_finished() {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Visible,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
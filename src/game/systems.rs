@@ -1,19 +1,30 @@
-use crate::game::barricade::{Barricade, CollisionShape, ObstacleType};
+use crate::game::action_map::{Action, ActionMap, ActionState};
+use crate::game::barricade::{Barricade, CollisionShape, OVERHEAD_BOTTOM, ObstacleType};
+use crate::game::chaser::{Stumble, bounce_track, stumble};
 use crate::game::coin::Coin;
+use crate::game::collision::{
+    DeathCause, HitOutcome, classify_barricade_hit, classify_train_hit, resolve_hit,
+};
+use crate::game::events::{
+    Cause, CauseKind, Jumped, Landed, LaneChanged, NearMiss, ObstacleCleared, PlayerDied, Slid,
+    Stumbled,
+};
 use crate::game::game_state::{GameOverText, GameState};
-use crate::game::input::PlayerInput;
+use crate::game::hoverboard::{Hoverboard, Invulnerable, absorb_hit};
+use crate::game::input::{BufferedAction, InputBuffer, PlayerInput};
 use crate::game::player::{
-    AnimationState, CurrentTrack, LeftArm, LeftLeg, Player, RightArm, RightLeg, SlideTimer,
-    Velocity, spawn_player,
+    AnimationState, Crouched, CurrentTrack, GroundContact, LandingTimer, LaneSwitch,
+    LastLaneChange, LeftArm, LeftLeg, PLAYER_HALF_WIDTH, Player, PlayerModel, RightArm, RightLeg,
+    SlideTimer, Velocity, crouch, half_height, spawn_player, stand_up, standing_collider,
 };
-use crate::game::props::generate_props;
-use crate::game::track::{
-    SEGMENT_LENGTH, TrackSegment, generate_track_segments, spawn_track_segment,
-};
-use crate::game::train::{Train, TrainPart, TrainRamp, TrainTop, TrainType};
+use crate::game::track::{TrackMeshes, TrackSegment, generate_track_segments};
+use crate::game::train::{Smashed, TRAIN_WIDTH, Train, TrainType, car_at};
+use crate::resources::daily::DailyRun;
 use crate::resources::game_config::GameConfig;
+use crate::resources::lane_layout::LaneLayout;
+use crate::resources::profile::PlayerProfile;
 use crate::resources::score::Score;
-use crate::resources::track_pool::TrackPool;
+use crate::resources::theme::Themes;
 use avian3d::prelude::*;
 use bevy::prelude::*;
 
@@ -22,14 +33,18 @@ pub fn setup_tracks(
     meshes: ResMut<Assets<Mesh>>,
     materials: ResMut<Assets<StandardMaterial>>,
     track_pool: ResMut<crate::resources::track_pool::TrackPool>,
-    game_config: Res<crate::resources::game_config::GameConfig>,
+    track_meshes: ResMut<TrackMeshes>,
+    lane_layout: Res<LaneLayout>,
+    themes: Res<Themes>,
 ) {
     generate_track_segments(
         commands.reborrow(),
         meshes,
         materials,
         track_pool,
-        game_config,
+        track_meshes,
+        lane_layout,
+        themes,
     );
 
     // Spawn ground plane for physics collision
@@ -40,150 +55,480 @@ pub fn setup_tracks(
     ));
 }
 
-pub fn setup_props(
-    commands: Commands,
-    meshes: ResMut<Assets<Mesh>>,
-    materials: ResMut<Assets<StandardMaterial>>,
-    game_config: Res<crate::resources::game_config::GameConfig>,
-) {
-    generate_props(commands, meshes, materials, game_config);
-}
-
 pub fn setup_player(
     commands: Commands,
     meshes: ResMut<Assets<Mesh>>,
     materials: ResMut<Assets<StandardMaterial>>,
     game_config: Res<crate::resources::game_config::GameConfig>,
+    lane_layout: Res<LaneLayout>,
 ) {
-    spawn_player(commands, meshes, materials, game_config);
+    spawn_player(commands, meshes, materials, game_config, lane_layout);
 }
 
+// Seconds ahead at the current speed to check for lanes closing
+const LANE_CLOSE_LOOKAHEAD: f32 = 0.6;
+// How far clear of an obstacle's side still counts as a near miss
+const NEAR_MISS_GAP: f32 = 0.5;
+
+/// Marks an obstacle the player has got past, or been tripped by, so it's only reported once
+#[derive(Component)]
+pub struct Passed;
+
 pub fn move_player_forward(
     mut player_query: Query<&mut Transform, (With<Player>, Without<Camera3d>)>,
-    velocity_query: Query<&Velocity, With<Player>>,
+    velocity_query: Query<(&Velocity, &GroundContact), With<Player>>,
     time: Res<Time>,
 ) {
-    if let Ok(velocity) = velocity_query.single() {
+    if let Ok((velocity, ground_contact)) = velocity_query.single() {
         for mut transform in player_query.iter_mut() {
-            transform.translation.z += velocity.forward * time.delta_secs();
+            let speed = velocity.forward + ground_contact.platform_velocity;
+            transform.translation.z += speed * time.delta_secs();
         }
     }
 }
 
+/// Keeps the furthest distance reached this run for the score
+pub fn update_run_distance(
+    mut score: ResMut<Score>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    if let Ok(transform) = player_query.single() {
+        score.distance = score.distance.max(transform.translation.z);
+    }
+}
+
 pub fn accelerate_speed(
     mut game_config: ResMut<GameConfig>,
-    mut velocity_query: Query<&mut Velocity, With<Player>>,
+    mut velocity_query: Query<(&mut Velocity, Option<&Stumble>), With<Player>>,
+    daily_run: Res<DailyRun>,
     time: Res<Time>,
 ) {
-    game_config.current_speed += game_config.speed_acceleration_rate * time.delta_secs();
+    game_config.current_speed += game_config.speed_acceleration_rate
+        * daily_run.acceleration_factor(&game_config)
+        * time.delta_secs();
 
-    for mut velocity in velocity_query.iter_mut() {
-        velocity.forward = game_config.current_speed;
+    for (mut velocity, stumble) in velocity_query.iter_mut() {
+        let speed_factor = stumble.map_or(1.0, |stumble| stumble.speed_factor(&game_config));
+        velocity.forward = game_config.current_speed * speed_factor;
     }
 }
 
 pub fn handle_track_switching(
-    mut player_query: Query<(&mut CurrentTrack, &mut Transform), (With<Player>, Without<Camera3d>)>,
+    mut commands: Commands,
+    mut player_query: Query<
+        (Entity, &mut CurrentTrack, &mut LastLaneChange, &Transform),
+        (With<Player>, Without<Camera3d>),
+    >,
     player_input: Res<PlayerInput>,
-    animation_query: Query<&AnimationState, With<Player>>,
+    lane_layout: Res<LaneLayout>,
     game_config: Res<GameConfig>,
+    mut lane_changes: MessageWriter<LaneChanged>,
 ) {
-    if let Ok(animation_state) = animation_query.single() {
-        if *animation_state != AnimationState::Running {
+    if let Ok((entity, mut current_track, mut lane_change, transform)) = player_query.single_mut() {
+        let previous_track = *current_track;
+
+        // Lane indices grow towards +x, which is the left of the screen
+        let mut target = previous_track.0 as i16;
+        if player_input.move_left {
+            target += 1;
+        }
+        if player_input.move_right {
+            target -= 1;
+        }
+        let target = target.clamp(0, lane_layout.count as i16 - 1) as u8;
+
+        // Lanes that are closed here or just ahead can't be entered
+        let player_z = transform.translation.z;
+        let lookahead = player_z + game_config.current_speed * LANE_CLOSE_LOOKAHEAD;
+        if target == previous_track.0 || !lane_layout.is_open_between(target, player_z, lookahead) {
             return;
         }
-    } else {
+
+        *current_track = CurrentTrack(target);
+        lane_change.from = previous_track;
+        // Tween from wherever the player is now, so reversing mid-switch turns around smoothly
+        commands.entity(entity).insert(LaneSwitch::new(
+            previous_track,
+            transform.translation.x,
+            current_track.x_position(&lane_layout),
+            &lane_layout,
+            &game_config,
+        ));
+        lane_changes.write(LaneChanged {
+            cause: Cause::player(entity),
+            from: previous_track.0,
+            to: target,
+        });
+    }
+}
+
+/// Steers the player out of a lane that closes just ahead, such as at a narrowing bridge
+pub fn force_out_of_closed_lanes(
+    mut commands: Commands,
+    mut player_query: Query<
+        (Entity, &mut CurrentTrack, &mut LastLaneChange, &Transform),
+        (With<Player>, Without<Camera3d>),
+    >,
+    lane_layout: Res<LaneLayout>,
+    game_config: Res<GameConfig>,
+    mut lane_changes: MessageWriter<LaneChanged>,
+) {
+    let Ok((entity, mut current_track, mut lane_change, transform)) = player_query.single_mut()
+    else {
+        return;
+    };
+
+    let player_z = transform.translation.z;
+    let lookahead = player_z + game_config.current_speed * LANE_CLOSE_LOOKAHEAD;
+    if lane_layout.is_open_between(current_track.0, player_z, lookahead) {
         return;
     }
+    let Some(target) = lane_layout.nearest_open_between(current_track.0, player_z, lookahead)
+    else {
+        return;
+    };
 
-    if let Ok((mut current_track, mut transform)) = player_query.single_mut() {
-        let track_spacing = game_config.track_spacing;
+    let previous_track = *current_track;
+    *current_track = CurrentTrack(target);
+    lane_change.from = previous_track;
+    commands.entity(entity).insert(LaneSwitch::new(
+        previous_track,
+        transform.translation.x,
+        current_track.x_position(&lane_layout),
+        &lane_layout,
+        &game_config,
+    ));
+    lane_changes.write(LaneChanged {
+        cause: Cause {
+            entity,
+            kind: CauseKind::ClosedLane,
+        },
+        from: previous_track.0,
+        to: target,
+    });
+}
 
-        if player_input.move_left {
-            match *current_track {
-                CurrentTrack::Left => {
-                    *current_track = CurrentTrack::Middle;
-                }
-                CurrentTrack::Middle => {
-                    *current_track = CurrentTrack::Right;
-                }
-                CurrentTrack::Right => {}
-            }
+pub fn tween_lane_switch(
+    mut commands: Commands,
+    mut player_query: Query<
+        (
+            Entity,
+            &CurrentTrack,
+            &mut Transform,
+            Option<&mut LaneSwitch>,
+        ),
+        (With<Player>, Without<Camera3d>),
+    >,
+    mut model_query: Query<&mut Transform, (With<PlayerModel>, Without<Player>)>,
+    lane_layout: Res<LaneLayout>,
+    game_config: Res<GameConfig>,
+    time: Res<Time>,
+) {
+    let Ok((entity, current_track, mut transform, lane_switch)) = player_query.single_mut() else {
+        return;
+    };
+
+    let target_x = current_track.x_position(&lane_layout);
+    let mut roll = 0.0;
+
+    if let Some(mut lane_switch) = lane_switch {
+        lane_switch.elapsed += time.delta_secs();
+        let t = (lane_switch.elapsed / lane_switch.duration).min(1.0);
+        let eased = t * t * (3.0 - 2.0 * t);
+        transform.translation.x = lane_switch.start_x + (target_x - lane_switch.start_x) * eased;
+
+        // Lean into the direction of travel, peaking halfway across
+        let direction = (target_x - lane_switch.start_x).signum();
+        roll = -direction * game_config.lane_switch_lean * (t * std::f32::consts::PI).sin();
+
+        if t >= 1.0 {
+            commands.entity(entity).remove::<LaneSwitch>();
         }
+    } else {
+        transform.translation.x = target_x;
+    }
 
-        if player_input.move_right {
-            match *current_track {
-                CurrentTrack::Left => {}
-                CurrentTrack::Middle => {
-                    *current_track = CurrentTrack::Left;
-                }
-                CurrentTrack::Right => {
-                    *current_track = CurrentTrack::Middle;
-                }
-            }
+    for mut model_transform in model_query.iter_mut() {
+        model_transform.rotation = Quat::from_rotation_z(roll);
+    }
+}
+
+pub fn update_ground_contact(
+    mut player_query: Query<(&mut GroundContact, &ShapeHits, &LinearVelocity), With<Player>>,
+    collider_query: Query<&ColliderOf>,
+    train_query: Query<&Train>,
+    time: Res<Time>,
+) {
+    for (mut ground_contact, shape_hits, linear_velocity) in player_query.iter_mut() {
+        // Ignore the probe while still moving upwards off the ground after a jump
+        ground_contact.grounded = !shape_hits.is_empty() && linear_velocity.y <= 0.5;
+        if ground_contact.grounded {
+            ground_contact.last_grounded_at = time.elapsed_secs();
         }
 
-        // Instant snap to target track position
-        let target_x = (current_track.as_index() as f32 - 1.0) * track_spacing;
-        transform.translation.x = target_x;
+        // Standing on a train roof or ramp carries the player along with the train
+        let platform = if ground_contact.grounded {
+            shape_hits.iter().find_map(|hit| {
+                let body = collider_query.get(hit.entity).ok()?.body;
+                train_query.get(body).ok().map(|train| train.velocity_z)
+            })
+        } else {
+            None
+        };
+        ground_contact.on_train = platform.is_some();
+        ground_contact.platform_velocity = platform.unwrap_or(0.0);
     }
 }
 
+fn start_slide(
+    commands: &mut Commands,
+    entity: Entity,
+    animation_state: &mut AnimationState,
+    transform: &mut Transform,
+    game_config: &GameConfig,
+) {
+    *animation_state = AnimationState::Sliding;
+    crouch(commands, entity, transform);
+    commands.entity(entity).insert(SlideTimer {
+        timer: Timer::from_seconds(game_config.slide_max_duration, TimerMode::Once),
+    });
+}
+
+/// Whether a tunnel roof is directly above the player, so standing up isn't possible
+fn under_overhead(
+    player_pos: Vec3,
+    barricade_query: &Query<(&Transform, &Barricade, &CollisionShape), Without<Player>>,
+) -> bool {
+    barricade_query
+        .iter()
+        .any(|(barricade_transform, barricade, collision_shape)| {
+            let barricade_pos = barricade_transform.translation;
+            barricade.obstacle_type == ObstacleType::LowTunnel
+                && (player_pos.x - barricade_pos.x).abs()
+                    < collision_shape.size.x / 2.0 + PLAYER_HALF_WIDTH
+                && (player_pos.z - barricade_pos.z).abs() < collision_shape.size.z / 2.0 + 0.5
+        })
+}
+
+fn start_jump(
+    animation_state: &mut AnimationState,
+    linear_velocity: &mut LinearVelocity,
+    ground_contact: &mut GroundContact,
+) {
+    *animation_state = AnimationState::Jumping;
+    linear_velocity.y = 10.0; // Jump impulse
+    // Spend the coyote window so it can't grant a second jump
+    ground_contact.grounded = false;
+    ground_contact.last_grounded_at = f32::NEG_INFINITY;
+}
+
+/// Movement state machine: Running, Jumping, Falling, Rolling, Landing and Sliding.
+/// Sliding normally ends through `handle_slide_timer`; a jump cancels it early.
 pub fn handle_slide_jump_input(
     mut commands: Commands,
     mut player_query: Query<
-        (Entity, &mut AnimationState, &mut LinearVelocity, &Transform),
+        (
+            Entity,
+            &mut AnimationState,
+            &mut LinearVelocity,
+            &mut GroundContact,
+            &mut Transform,
+            Option<&mut LandingTimer>,
+        ),
         With<Player>,
     >,
-    player_input: Res<PlayerInput>,
+    barricade_query: Query<(&Transform, &Barricade, &CollisionShape), Without<Player>>,
+    mut input_buffer: ResMut<InputBuffer>,
+    game_config: Res<GameConfig>,
+    time: Res<Time>,
+    mut jumps: MessageWriter<Jumped>,
+    mut slides: MessageWriter<Slid>,
+    mut landings: MessageWriter<Landed>,
 ) {
-    if let Ok((entity, mut animation_state, mut linear_velocity, transform)) =
-        player_query.single_mut()
-    {
-        // Check if grounded (Y position near ground, accounting for player size)
-        let is_grounded = transform.translation.y < 1.6;
+    let Ok((
+        entity,
+        mut animation_state,
+        mut linear_velocity,
+        mut ground_contact,
+        mut transform,
+        landing_timer,
+    )) = player_query.single_mut()
+    else {
+        return;
+    };
 
-        if is_grounded && *animation_state == AnimationState::Jumping {
-            *animation_state = AnimationState::Running;
-        }
+    let now = time.elapsed_secs();
+    let window = game_config.input_buffer_window;
+    let previous_state = *animation_state;
 
-        if *animation_state == AnimationState::Running {
-            if player_input.slide {
-                *animation_state = AnimationState::Sliding;
-                // Add slide timer (0.5 seconds)
-                commands.entity(entity).insert(SlideTimer {
-                    timer: Timer::from_seconds(0.5, TimerMode::Once),
+    match *animation_state {
+        AnimationState::Running => {
+            if input_buffer.consume(BufferedAction::Slide, now, window) {
+                start_slide(
+                    &mut commands,
+                    entity,
+                    &mut animation_state,
+                    &mut transform,
+                    &game_config,
+                );
+            } else if ground_contact.can_jump(now, game_config.coyote_time)
+                && input_buffer.consume(BufferedAction::Jump, now, window)
+            {
+                start_jump(
+                    &mut animation_state,
+                    &mut linear_velocity,
+                    &mut ground_contact,
+                );
+            } else if !ground_contact.can_jump(now, game_config.coyote_time) {
+                // Ran off a roof or ramp edge
+                *animation_state = AnimationState::Falling;
+            }
+        }
+        AnimationState::Jumping | AnimationState::Falling => {
+            if input_buffer.consume(BufferedAction::Slide, now, window) {
+                *animation_state = AnimationState::Rolling;
+                linear_velocity.y = -game_config.fast_fall_speed;
+            } else if ground_contact.grounded {
+                *animation_state = AnimationState::Landing;
+                commands.entity(entity).insert(LandingTimer {
+                    timer: Timer::from_seconds(game_config.landing_duration, TimerMode::Once),
                 });
-            } else if player_input.jump && is_grounded {
-                *animation_state = AnimationState::Jumping;
-                linear_velocity.y = 10.0; // Jump impulse
+            } else if *animation_state == AnimationState::Jumping && linear_velocity.y <= 0.0 {
+                *animation_state = AnimationState::Falling;
+            }
+        }
+        AnimationState::Rolling => {
+            if ground_contact.grounded {
+                // Chain the slam straight into a slide
+                start_slide(
+                    &mut commands,
+                    entity,
+                    &mut animation_state,
+                    &mut transform,
+                    &game_config,
+                );
+            } else {
+                linear_velocity.y = linear_velocity.y.min(-game_config.fast_fall_speed);
+            }
+        }
+        AnimationState::Landing => {
+            let landed = landing_timer.is_none_or(|mut landing_timer| {
+                landing_timer.timer.tick(time.delta());
+                landing_timer.timer.is_finished()
+            });
+
+            if input_buffer.consume(BufferedAction::Slide, now, window) {
+                commands.entity(entity).remove::<LandingTimer>();
+                start_slide(
+                    &mut commands,
+                    entity,
+                    &mut animation_state,
+                    &mut transform,
+                    &game_config,
+                );
+            } else if input_buffer.consume(BufferedAction::Jump, now, window) {
+                commands.entity(entity).remove::<LandingTimer>();
+                start_jump(
+                    &mut animation_state,
+                    &mut linear_velocity,
+                    &mut ground_contact,
+                );
+            } else if landed {
+                commands.entity(entity).remove::<LandingTimer>();
+                *animation_state = AnimationState::Running;
+            }
+        }
+        AnimationState::Sliding => {
+            if ground_contact.can_jump(now, game_config.coyote_time)
+                && !under_overhead(transform.translation, &barricade_query)
+                && input_buffer.consume(BufferedAction::Jump, now, window)
+            {
+                commands.entity(entity).remove::<SlideTimer>();
+                stand_up(&mut commands, entity, &mut transform);
+                start_jump(
+                    &mut animation_state,
+                    &mut linear_velocity,
+                    &mut ground_contact,
+                );
             }
         }
     }
+
+    if *animation_state == previous_state {
+        return;
+    }
+    let cause = Cause::player(entity);
+    let airborne = matches!(
+        previous_state,
+        AnimationState::Jumping | AnimationState::Falling | AnimationState::Rolling
+    );
+    match *animation_state {
+        AnimationState::Jumping => {
+            jumps.write(Jumped { cause });
+        }
+        AnimationState::Landing => {
+            landings.write(Landed { cause });
+        }
+        AnimationState::Sliding => {
+            // A roll lands straight into a slide
+            if airborne {
+                landings.write(Landed { cause });
+            }
+            slides.write(Slid { cause });
+        }
+        _ => {}
+    }
 }
 
 pub fn handle_slide_timer(
     mut commands: Commands,
-    mut player_query: Query<(Entity, &mut AnimationState, &mut SlideTimer), With<Player>>,
+    mut player_query: Query<
+        (Entity, &mut AnimationState, &mut SlideTimer, &mut Transform),
+        With<Player>,
+    >,
+    barricade_query: Query<(&Transform, &Barricade, &CollisionShape), Without<Player>>,
+    player_input: Res<PlayerInput>,
+    game_config: Res<GameConfig>,
     time: Res<Time>,
 ) {
-    for (entity, mut animation_state, mut slide_timer) in player_query.iter_mut() {
+    for (entity, mut animation_state, mut slide_timer, mut transform) in player_query.iter_mut() {
         slide_timer.timer.tick(time.delta());
-        if slide_timer.timer.just_finished() {
+
+        let released = !player_input.slide_held
+            && slide_timer.timer.elapsed_secs() >= game_config.slide_min_duration;
+        let slide_over = released || slide_timer.timer.is_finished();
+
+        // A tunnel roof keeps the player down however long the slide has lasted
+        if slide_over && !under_overhead(transform.translation, &barricade_query) {
             *animation_state = AnimationState::Running;
             commands.entity(entity).remove::<SlideTimer>();
+            stand_up(&mut commands, entity, &mut transform);
         }
     }
 }
 
-pub fn handle_animations(mut player_query: Query<(&AnimationState, &mut Transform), With<Player>>) {
-    for (animation_state, mut transform) in player_query.iter_mut() {
+pub fn handle_animations(
+    player_query: Query<&AnimationState, With<Player>>,
+    mut model_query: Query<&mut Transform, With<PlayerModel>>,
+) {
+    let Ok(animation_state) = player_query.single() else {
+        return;
+    };
+
+    for mut transform in model_query.iter_mut() {
         match *animation_state {
             AnimationState::Sliding => {
+                // The collider is already crouched, squash the model to match
                 transform.scale.y = 0.5;
+                transform.translation.y = -0.2;
             }
-            AnimationState::Running | AnimationState::Jumping => {
+            AnimationState::Running
+            | AnimationState::Jumping
+            | AnimationState::Falling
+            | AnimationState::Rolling
+            | AnimationState::Landing => {
                 transform.scale.y = 1.0;
+                transform.translation.y = 0.0;
             }
         }
     }
@@ -278,7 +623,7 @@ pub fn animate_player_limbs(
                 transform.rotation = Quat::from_rotation_x(1.2);
             }
         }
-        AnimationState::Jumping => {
+        AnimationState::Jumping | AnimationState::Falling => {
             // Jumping animation: arms up, legs slightly tucked
             if let Ok(mut transform) = left_arm_query.single_mut() {
                 transform.rotation = Quat::from_rotation_x(-0.5) * Quat::from_rotation_z(0.3);
@@ -293,50 +638,119 @@ pub fn animate_player_limbs(
                 transform.rotation = Quat::from_rotation_x(0.3);
             }
         }
+        AnimationState::Rolling => {
+            // Rolling animation: tucked into a ball for the slam
+            if let Ok(mut transform) = left_arm_query.single_mut() {
+                transform.rotation = Quat::from_rotation_x(-1.3);
+            }
+            if let Ok(mut transform) = right_arm_query.single_mut() {
+                transform.rotation = Quat::from_rotation_x(-1.3);
+            }
+            if let Ok(mut transform) = left_leg_query.single_mut() {
+                transform.rotation = Quat::from_rotation_x(-1.4);
+            }
+            if let Ok(mut transform) = right_leg_query.single_mut() {
+                transform.rotation = Quat::from_rotation_x(-1.4);
+            }
+        }
+        AnimationState::Landing => {
+            // Landing animation: knees bent to absorb the impact, arms out for balance
+            if let Ok(mut transform) = left_arm_query.single_mut() {
+                transform.rotation = Quat::from_rotation_z(0.6);
+            }
+            if let Ok(mut transform) = right_arm_query.single_mut() {
+                transform.rotation = Quat::from_rotation_z(-0.6);
+            }
+            if let Ok(mut transform) = left_leg_query.single_mut() {
+                transform.rotation = Quat::from_rotation_x(-0.6);
+            }
+            if let Ok(mut transform) = right_leg_query.single_mut() {
+                transform.rotation = Quat::from_rotation_x(-0.6);
+            }
+        }
     }
 }
 
 pub fn detect_collisions(
-    mut next_state: ResMut<NextState<GameState>>,
-    player_query: Query<(&Transform, &CurrentTrack, &AnimationState), With<Player>>,
-    barricade_query: Query<(&Transform, &Barricade, &CollisionShape), Without<Player>>,
+    mut commands: Commands,
+    mut stumbles: MessageWriter<Stumbled>,
+    mut deaths: MessageWriter<PlayerDied>,
+    player_query: Query<
+        (
+            Entity,
+            &Transform,
+            &CurrentTrack,
+            Has<Crouched>,
+            &LastLaneChange,
+            Option<&LaneSwitch>,
+            Option<&Hoverboard>,
+            Has<Invulnerable>,
+            Option<&Stumble>,
+        ),
+        With<Player>,
+    >,
+    barricade_query: Query<(Entity, &Transform, &Barricade, &CollisionShape), Without<Player>>,
+    lane_layout: Res<LaneLayout>,
+    game_config: Res<GameConfig>,
 ) {
-    if let Ok((player_transform, player_track, animation_state)) = player_query.single() {
+    if let Ok((
+        player_entity,
+        player_transform,
+        player_track,
+        crouched,
+        lane_change,
+        lane_switch,
+        hoverboard,
+        invulnerable,
+        stumble,
+    )) = player_query.single()
+    {
+        if invulnerable {
+            return;
+        }
+        let stumbling = stumble.is_some();
+
         let player_pos = player_transform.translation;
-        let player_track_index = player_track.as_index();
         let player_y = player_pos.y;
 
-        for (barricade_transform, barricade, collision_shape) in barricade_query.iter() {
-            if barricade.track_index == player_track_index {
-                let barricade_pos = barricade_transform.translation;
-
-                // Check Z distance (are we at the obstacle?)
+        for (barricade_entity, barricade_transform, barricade, collision_shape) in
+            barricade_query.iter()
+        {
+            // The obstacle that tripped the player can't trip them again while they bounce off
+            if stumble.is_some_and(|stumble| stumble.ignores(barricade_entity)) {
+                continue;
+            }
+            // Lateral overlap rather than lane index, so a half-finished lane switch still counts
+            let barricade_pos = barricade_transform.translation;
+            let lateral_offset = (player_pos.x - barricade_pos.x).abs();
+            let half_width = collision_shape.size.x / 2.0;
+            if lateral_offset < half_width + PLAYER_HALF_WIDTH {
+                // Check Z distance (are we at the obstacle?) - tunnels span several meters
                 let z_distance = (player_pos.z - barricade_pos.z).abs();
-                if z_distance > 0.8 {
+                if z_distance > collision_shape.size.z / 2.0 + 0.65 {
                     continue; // Not close enough yet
                 }
 
+                // Vertical extent of the player's current collider
+                let player_half_height = half_height(crouched);
+                let player_top = player_y + player_half_height;
+                let player_bottom = player_y - player_half_height;
+
                 // Check if player avoided the obstacle based on position
                 let hit = match barricade.obstacle_type {
                     ObstacleType::JumpOver => {
                         // Low obstacle (bar at y ~0.45, height ~0.7)
                         // Player must be high enough to clear it
                         let obstacle_top = 0.45 + collision_shape.size.y / 2.0;
-                        let player_bottom = player_y - 0.8; // Player's feet
                         player_bottom < obstacle_top
                     }
-                    ObstacleType::SlideUnder => {
-                        // High obstacle (sign at y ~1.5, height ~0.6)
-                        // Player can either slide UNDER or jump OVER
-                        let obstacle_bottom = 1.2; // Bottom of the sign
-                        let obstacle_top = 1.9; // Top of the sign (including lights)
-
-                        let (player_top, player_bottom) =
-                            if *animation_state == AnimationState::Sliding {
-                                (player_y + 0.4, player_y) // Sliding - very low
-                            } else {
-                                (player_y + 1.0, player_y - 0.8) // Standing/jumping height
-                            };
+                    ObstacleType::SlideUnder | ObstacleType::LowTunnel => {
+                        // Overhead obstacle: player can either slide UNDER or jump OVER
+                        let obstacle_bottom = OVERHEAD_BOTTOM;
+                        let obstacle_top = match barricade.obstacle_type {
+                            ObstacleType::LowTunnel => OVERHEAD_BOTTOM + collision_shape.size.y,
+                            _ => 1.9, // Top of the sign (including lights)
+                        };
 
                         // Collision only if player overlaps with obstacle vertically
                         // No hit if player is completely above OR completely below
@@ -348,7 +762,44 @@ pub fn detect_collisions(
                 };
 
                 if hit {
-                    next_state.set(GameState::GameOver);
+                    let cause = Cause::barricade(barricade_entity, barricade);
+                    let kind = classify_barricade_hit(lateral_offset, half_width);
+                    match resolve_hit(kind, hoverboard.is_some(), stumbling) {
+                        HitOutcome::Stumbled => {
+                            let bounce = bounce_track(
+                                *player_track,
+                                lane_switch,
+                                lane_change,
+                                barricade_pos.x,
+                                &lane_layout,
+                            );
+                            commands.entity(player_entity).insert(stumble(
+                                player_pos.x,
+                                *player_track,
+                                bounce,
+                                barricade_entity,
+                                &lane_layout,
+                                &game_config,
+                            ));
+                            stumbles.write(Stumbled { cause });
+                        }
+                        HitOutcome::Absorbed => {
+                            if let Some(hoverboard) = hoverboard {
+                                absorb_hit(&mut commands, player_entity, hoverboard, &game_config);
+                                commands.entity(barricade_entity).despawn();
+                            }
+                        }
+                        HitOutcome::Died => {
+                            deaths.write(PlayerDied {
+                                cause,
+                                death: DeathCause::from_barricade(
+                                    barricade.obstacle_type,
+                                    kind,
+                                    stumbling,
+                                ),
+                            });
+                        }
+                    }
                     return;
                 }
             }
@@ -357,22 +808,59 @@ pub fn detect_collisions(
 }
 
 pub fn detect_train_collisions(
-    mut next_state: ResMut<NextState<GameState>>,
-    player_query: Query<(&Transform, &CurrentTrack), With<Player>>,
-    train_query: Query<(&Transform, &Train), Without<Player>>,
+    mut commands: Commands,
+    mut stumbles: MessageWriter<Stumbled>,
+    mut deaths: MessageWriter<PlayerDied>,
+    player_query: Query<
+        (
+            Entity,
+            &Transform,
+            &CurrentTrack,
+            Has<Crouched>,
+            &LastLaneChange,
+            Option<&LaneSwitch>,
+            Option<&Hoverboard>,
+            Has<Invulnerable>,
+            Option<&Stumble>,
+        ),
+        With<Player>,
+    >,
+    train_query: Query<(Entity, &Transform, &Train, Option<&Smashed>), Without<Player>>,
+    lane_layout: Res<LaneLayout>,
+    game_config: Res<GameConfig>,
 ) {
-    if let Ok((player_transform, player_track)) = player_query.single() {
+    if let Ok((
+        player_entity,
+        player_transform,
+        player_track,
+        crouched,
+        lane_change,
+        lane_switch,
+        hoverboard,
+        invulnerable,
+        stumble,
+    )) = player_query.single()
+    {
+        if invulnerable {
+            return;
+        }
+        let stumbling = stumble.is_some();
+
         let player_pos = player_transform.translation;
-        let player_track_index = player_track.as_index();
         let player_y = player_pos.y;
 
-        for (train_transform, train) in train_query.iter() {
-            // Only check trains on the same track
-            if train.track_index != player_track_index {
+        for (train_entity, train_transform, train, smashed) in train_query.iter() {
+            if stumble.is_some_and(|stumble| stumble.ignores(train_entity)) {
                 continue;
             }
-
             let train_pos = train_transform.translation;
+
+            // Only check trains the player overlaps sideways, including mid lane switch
+            let lateral_offset = (player_pos.x - train_pos.x).abs();
+            if lateral_offset >= TRAIN_WIDTH / 2.0 + PLAYER_HALF_WIDTH {
+                continue;
+            }
+
             let train_half_length = train.length / 2.0;
 
             // Check if player is within train's Z range
@@ -385,7 +873,7 @@ pub fn detect_train_collisions(
             let train_top = train_height + 0.1; // Top of train (matched to physics ~2.3)
 
             // Check if player is ON TOP of the train
-            let player_bottom = player_y - 0.8;
+            let player_bottom = player_y - half_height(crouched);
 
             // Ramp-specific logic for StationaryWithRamp trains
             if train.train_type == TrainType::StationaryWithRamp {
@@ -433,29 +921,141 @@ pub fn detect_train_collisions(
                 continue; // Not near the train body
             }
 
+            // A car smashed by a hoverboard leaves a gap to run through
+            let car = car_at(train, player_z - train_back);
+            if smashed.is_some_and(|smashed| smashed.cars.contains(&car)) {
+                continue;
+            }
+
             // Check if player is colliding with train body (only if at ground level)
             if player_bottom < train_top && player_y < train_top {
                 // Collision with train!
-                next_state.set(GameState::GameOver);
+                let cause = Cause::train(train_entity, train);
+                let kind = classify_train_hit(player_z, train_back, lateral_offset);
+                match resolve_hit(kind, hoverboard.is_some(), stumbling) {
+                    HitOutcome::Stumbled => {
+                        let bounce = bounce_track(
+                            *player_track,
+                            lane_switch,
+                            lane_change,
+                            train_pos.x,
+                            &lane_layout,
+                        );
+                        commands.entity(player_entity).insert(stumble(
+                            player_pos.x,
+                            *player_track,
+                            bounce,
+                            train_entity,
+                            &lane_layout,
+                            &game_config,
+                        ));
+                        stumbles.write(Stumbled { cause });
+                    }
+                    HitOutcome::Absorbed => {
+                        if let Some(hoverboard) = hoverboard {
+                            absorb_hit(&mut commands, player_entity, hoverboard, &game_config);
+                            // Only the car that was hit goes; the roof and ramp stay
+                            let mut smashed = smashed.cloned().unwrap_or_default();
+                            smashed.cars.push(car);
+                            commands.entity(train_entity).insert(smashed);
+                        }
+                    }
+                    HitOutcome::Died => {
+                        deaths.write(PlayerDied {
+                            cause,
+                            death: DeathCause::from_train(player_z, train_back, kind, stumbling),
+                        });
+                    }
+                }
                 return;
             }
         }
     }
 }
 
+/// Ends the run when the player dies, keeping what killed them for the game over screen
+pub fn end_run_on_death(
+    mut deaths: MessageReader<PlayerDied>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut death_cause: ResMut<DeathCause>,
+) {
+    if let Some(death) = deaths.read().last() {
+        *death_cause = death.death;
+        next_state.set(GameState::GameOver);
+    }
+}
+
+/// Reports obstacles the player cleared in their lane, or only just missed beside it
+pub fn detect_passed_obstacles(
+    mut commands: Commands,
+    mut stumbles: MessageReader<Stumbled>,
+    player_query: Query<&Transform, With<Player>>,
+    barricade_query: Query<
+        (Entity, &Transform, &Barricade, &CollisionShape),
+        (Without<Passed>, Without<Player>),
+    >,
+    train_query: Query<(Entity, &Transform, &Train), (Without<Passed>, Without<Player>)>,
+    mut cleared: MessageWriter<ObstacleCleared>,
+    mut near_misses: MessageWriter<NearMiss>,
+) {
+    // An obstacle that tripped the player was neither cleared nor missed
+    for stumble in stumbles.read() {
+        commands.entity(stumble.cause.entity).try_insert(Passed);
+    }
+
+    let Ok(player_transform) = player_query.single() else {
+        return;
+    };
+    let player_pos = player_transform.translation;
+
+    // Each obstacle's cause, centre, and half its width and length
+    let barricades =
+        barricade_query
+            .iter()
+            .map(|(entity, transform, barricade, collision_shape)| {
+                (
+                    Cause::barricade(entity, barricade),
+                    transform.translation,
+                    Vec2::new(collision_shape.size.x, collision_shape.size.z) / 2.0,
+                )
+            });
+    let trains = train_query.iter().map(|(entity, transform, train)| {
+        (
+            Cause::train(entity, train),
+            transform.translation,
+            Vec2::new(TRAIN_WIDTH, train.length) / 2.0,
+        )
+    });
+
+    for (cause, position, half_size) in barricades.chain(trains) {
+        let lateral_offset = (player_pos.x - position.x).abs();
+        let contact = half_size.x + PLAYER_HALF_WIDTH;
+        let alongside = (player_pos.z - position.z).abs() <= half_size.y;
+
+        if alongside && lateral_offset >= contact && lateral_offset < contact + NEAR_MISS_GAP {
+            near_misses.write(NearMiss { cause });
+            commands.entity(cause.entity).insert(Passed);
+        } else if player_pos.z > position.z + half_size.y {
+            if lateral_offset < contact {
+                cleared.write(ObstacleCleared { cause });
+            }
+            commands.entity(cause.entity).insert(Passed);
+        }
+    }
+}
+
 pub fn handle_game_over_restart(
     mut next_state: ResMut<NextState<GameState>>,
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    action_state: Res<ActionState>,
     game_state: Res<State<GameState>>,
     ui_query: Query<Entity, With<GameOverText>>,
     mut game_config: ResMut<GameConfig>,
-    mut track_pool: ResMut<TrackPool>,
     mut score: ResMut<Score>,
+    mut daily_run: ResMut<DailyRun>,
     mut player_query: Query<
         (
+            Entity,
             &mut Transform,
             &mut CurrentTrack,
             &mut AnimationState,
@@ -463,82 +1063,45 @@ pub fn handle_game_over_restart(
         ),
         With<Player>,
     >,
-    barricade_query: Query<Entity, With<Barricade>>,
-    coin_query: Query<Entity, With<Coin>>,
-    train_related_query: Query<
-        Entity,
-        Or<(
-            With<Train>,
-            With<TrainRamp>,
-            With<TrainTop>,
-            With<TrainPart>,
-        )>,
-    >,
-    track_query: Query<Entity, With<TrackSegment>>,
+    barricade_and_coin_query: Query<Entity, Or<(With<Barricade>, With<Coin>)>>,
+    train_query: Query<Entity, With<Train>>,
+    mut lane_layout: ResMut<LaneLayout>,
     mut camera_query: Query<
         &mut Transform,
         (With<Camera3d>, Without<Player>, Without<TrackSegment>),
     >,
+    mut model_query: Query<&mut Transform, (With<PlayerModel>, Without<Player>, Without<Camera3d>)>,
 ) {
     if *game_state.get() == GameState::GameOver {
-        if keyboard_input.just_pressed(KeyCode::Space)
-            || keyboard_input.just_pressed(KeyCode::Enter)
-        {
-            for entity in ui_query.iter() {
-                commands.entity(entity).despawn();
+        let daily = action_state.just_pressed(Action::DailyRun);
+        if action_state.just_pressed(Action::Restart) || daily {
+            if daily {
+                daily_run.begin();
+            } else {
+                daily_run.active = false;
             }
 
-            for entity in barricade_query.iter() {
+            for entity in ui_query.iter() {
                 commands.entity(entity).despawn();
             }
 
-            // Despawn all coins
-            for entity in coin_query.iter() {
+            // Despawn all barricades and coins
+            for entity in barricade_and_coin_query.iter() {
                 commands.entity(entity).despawn();
             }
 
             // Reset score
             score.reset();
 
-            // Despawn all train-related entities (trains, ramps, tops, parts)
-            for entity in train_related_query.iter() {
+            // Despawn all trains (their ramps, tops and parts are children)
+            for entity in train_query.iter() {
                 commands.entity(entity).despawn();
             }
 
-            // Despawn all track segments
-            for entity in track_query.iter() {
-                commands.entity(entity).despawn();
-            }
-
-            // Reset track pool
-            track_pool.active_segments.clear();
-            track_pool.available_segments.clear();
-
-            // Regenerate initial tracks (inline to avoid type issues)
-            let track_spacing = game_config.track_spacing;
-            for segment_offset in -1..6i32 {
-                let z_position = (segment_offset as f32) * SEGMENT_LENGTH;
-
-                for track_index in 0..3u8 {
-                    let x_offset = (track_index as f32 - 1.0) * track_spacing;
-                    let segment_id = track_pool.active_segments.len() as u32;
+            // Bridges from the last run no longer apply; track is relaid on leaving game over
+            lane_layout.clear_sections();
 
-                    let track_entity = spawn_track_segment(
-                        &mut commands,
-                        &mut meshes,
-                        &mut materials,
-                        track_index,
-                        segment_id,
-                        x_offset,
-                        z_position,
-                    );
-
-                    track_pool.active_segments.push(track_entity);
-                }
-            }
-
-            game_config.current_speed = game_config.base_speed;
-            game_config.difficulty_scale = 1.0;
+            game_config.current_speed = daily_run.start_speed(&game_config);
 
             // Reset camera position to starting position
             if let Ok(mut camera_transform) = camera_query.single_mut() {
@@ -546,13 +1109,24 @@ pub fn handle_game_over_restart(
                 camera_transform.look_at(Vec3::new(0.0, 0.0, 10.0), Vec3::Y);
             }
 
-            if let Ok((mut transform, mut track, mut animation, mut velocity)) =
+            if let Ok((entity, mut transform, mut track, mut animation, mut velocity)) =
                 player_query.single_mut()
             {
-                transform.translation = Vec3::new(0.0, 1.5, 0.0);
-                *track = CurrentTrack::Middle;
+                *track = CurrentTrack(lane_layout.middle());
+                transform.translation = Vec3::new(track.x_position(&lane_layout), 1.5, 0.0);
+                // Physics never turns the player, but the crash replay writes its recorded
+                // poses back onto them
+                transform.rotation = Quat::IDENTITY;
                 *animation = AnimationState::Running;
-                velocity.forward = game_config.base_speed;
+                velocity.forward = game_config.current_speed;
+                commands
+                    .entity(entity)
+                    .remove::<(LaneSwitch, SlideTimer, Crouched)>()
+                    .insert(standing_collider());
+            }
+            // Straighten the lean from a lane switch the crash cut short
+            for mut model_transform in model_query.iter_mut() {
+                model_transform.rotation = Quat::IDENTITY;
             }
 
             next_state.set(GameState::Playing);
@@ -560,10 +1134,59 @@ pub fn handle_game_over_restart(
     }
 }
 
-pub fn show_game_over_on_state_change(mut commands: Commands, game_state: Res<State<GameState>>) {
+pub fn show_game_over_on_state_change(
+    mut commands: Commands,
+    game_state: Res<State<GameState>>,
+    action_map: Res<ActionMap>,
+    death_cause: Res<DeathCause>,
+    score: Res<Score>,
+    profile: Res<PlayerProfile>,
+    daily_run: Res<DailyRun>,
+    game_config: Res<GameConfig>,
+) {
     if game_state.is_changed() && *game_state.get() == GameState::GameOver {
+        let points = score.points(daily_run.multiplier(&profile));
+        let mut lines = vec![
+            "GAME OVER".to_string(),
+            death_cause.describe().to_string(),
+            format!("Score: {points} ({} m)", score.distance as u32),
+        ];
+        if daily_run.active {
+            lines.push(format!(
+                "Daily run {} - best today: {}",
+                daily_run.date,
+                profile.daily_best(daily_run.date)
+            ));
+            if game_config.daily_share_string {
+                let share = daily_run.share_string(points, score.distance);
+                info!("Daily run result: {share}");
+                lines.push(format!("Share: {share}"));
+            }
+        }
+        let modifiers: Vec<&str> = daily_run
+            .modifiers
+            .iter()
+            .map(|modifier| modifier.label())
+            .collect();
+        lines.extend([
+            format!("Press {} to restart", action_map.describe(Action::Restart)),
+            format!(
+                "Press {} for the daily run ({})",
+                action_map.describe(Action::DailyRun),
+                modifiers.join(", ")
+            ),
+            format!(
+                "Press {} for settings",
+                action_map.describe(Action::Settings)
+            ),
+            format!(
+                "Press {} for achievements",
+                action_map.describe(Action::Achievements)
+            ),
+        ]);
+
         commands.spawn((
-            Text::new("GAME OVER\nPress SPACE or ENTER to restart"),
+            Text::new(lines.join("\n")),
             Transform::from_xyz(0.0, 0.0, 100.0),
             GameOverText,
         ));
@@ -577,7 +1200,7 @@ pub struct CoinUI;
 pub fn setup_coin_ui(mut commands: Commands) {
     commands.spawn((
         CoinUI,
-        Text::new("Coins: 0"),
+        Text::new("Score: 0\nCoins: 0"),
         TextFont {
             font_size: 32.0,
             ..default()
@@ -592,8 +1215,21 @@ pub fn setup_coin_ui(mut commands: Commands) {
     ));
 }
 
-pub fn update_coin_ui(score: Res<Score>, mut query: Query<&mut Text, With<CoinUI>>) {
+pub fn update_coin_ui(
+    score: Res<Score>,
+    profile: Res<PlayerProfile>,
+    daily_run: Res<DailyRun>,
+    mut query: Query<&mut Text, With<CoinUI>>,
+) {
+    let multiplier = daily_run.multiplier(&profile);
     for mut text in query.iter_mut() {
-        **text = format!("Coins: {}", score.coins);
+        **text = format!(
+            "Score: {} x{multiplier}\nCoins: {}",
+            score.points(multiplier),
+            score.coins
+        );
+        if daily_run.active {
+            text.insert_str(0, &format!("Daily run {}\n", daily_run.date));
+        }
     }
 }
@@ -0,0 +1,149 @@
+use crate::game::path::OnPath;
+use crate::resources::day_cycle::DayCycle;
+use crate::resources::draw_horizon::DrawHorizon;
+use crate::resources::game_config::GameConfig;
+use crate::resources::lane_layout::LaneLayout;
+use crate::resources::theme::{TRANSITION_LENGTH, Themes};
+use bevy::prelude::*;
+
+/// Gantries spanning the track through a transition section, so the change of scenery
+/// reads as passing through a gateway rather than a fade
+#[derive(Component)]
+pub struct TransitionGantries {
+    pub start_z: f32,
+    pub end_z: f32,
+}
+
+const GANTRY_SPACING: f32 = 10.0;
+const GANTRY_HEIGHT: f32 = 5.5;
+
+// Light around sunrise and sunset, and after dark, laid over every theme
+const DUSK_SKY: Color = Color::srgb(0.95, 0.55, 0.35);
+const DUSK_SUN: Color = Color::srgb(1.0, 0.6, 0.35);
+const NIGHT_SKY: Color = Color::srgb(0.02, 0.03, 0.08);
+const MOONLIGHT: Color = Color::srgb(0.55, 0.62, 0.9);
+// Fraction of the theme's sun brightness the moon gives
+const MOON_ILLUMINANCE: f32 = 0.08;
+
+/// Sky, fog, sun and ambient light follow the theme at the camera and the time of day
+pub fn apply_theme_lighting(
+    themes: Res<Themes>,
+    day_cycle: Res<DayCycle>,
+    game_config: Res<GameConfig>,
+    mut camera_query: Query<(&Transform, &mut DistanceFog), With<Camera3d>>,
+    mut clear_color: ResMut<ClearColor>,
+    mut ambient_light: ResMut<AmbientLight>,
+    mut sun_query: Query<(&mut DirectionalLight, &mut Transform), Without<Camera3d>>,
+) {
+    let Ok((camera_transform, mut fog)) = camera_query.single_mut() else {
+        return;
+    };
+    let theme = themes.at(camera_transform.translation.z);
+    let daylight = day_cycle.daylight();
+    let twilight = day_cycle.twilight();
+
+    clear_color.0 = theme
+        .sky
+        .mix(&DUSK_SKY, twilight * 0.6)
+        .mix(&NIGHT_SKY, 1.0 - daylight);
+    // Fog fades into the sky, so whatever is still beyond it can't be seen against it
+    fog.color = clear_color.0;
+    ambient_light.color = theme.ambient_color.mix(&MOONLIGHT, 1.0 - daylight);
+    ambient_light.brightness = theme.ambient_brightness
+        * (game_config.night_ambient + (1.0 - game_config.night_ambient) * daylight);
+    for (mut sun, mut transform) in sun_query.iter_mut() {
+        sun.color = theme
+            .sun_color
+            .mix(&DUSK_SUN, twilight)
+            .mix(&MOONLIGHT, 1.0 - daylight);
+        sun.illuminance =
+            theme.sun_illuminance * (MOON_ILLUMINANCE + (1.0 - MOON_ILLUMINANCE) * daylight);
+        transform.rotation = day_cycle.sun_rotation();
+    }
+}
+
+/// Builds the gantries for the next transition section once it comes within view
+pub fn spawn_transition_gantries(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    themes: Res<Themes>,
+    lane_layout: Res<LaneLayout>,
+    draw_horizon: Res<DrawHorizon>,
+    gantry_query: Query<&TransitionGantries>,
+    camera_query: Query<&Transform, With<Camera3d>>,
+) {
+    let Ok(camera_transform) = camera_query.single() else {
+        return;
+    };
+    let camera_z = camera_transform.translation.z;
+
+    // Includes a section the camera is already inside
+    let start_z = themes.next_transition_after(camera_z - TRANSITION_LENGTH);
+    if start_z > camera_z + draw_horizon.distance
+        || gantry_query
+            .iter()
+            .any(|gantries| gantries.start_z == start_z)
+    {
+        return;
+    }
+
+    let span = 2.0 * (lane_layout.half_width() + 0.6);
+    let post_mesh = meshes.add(Cuboid::new(0.4, GANTRY_HEIGHT, 0.4));
+    let beam_mesh = meshes.add(Cuboid::new(span + 0.4, 0.6, 0.5));
+    let count = (TRANSITION_LENGTH / GANTRY_SPACING) as i32 + 1;
+
+    commands
+        .spawn((
+            TransitionGantries {
+                start_z,
+                end_z: start_z + TRANSITION_LENGTH,
+            },
+            OnPath,
+            Transform::from_xyz(0.0, 0.0, start_z),
+            Visibility::default(),
+        ))
+        .with_children(|parent| {
+            for i in 0..count {
+                let z = i as f32 * GANTRY_SPACING;
+                // Each gantry takes the blended colour of where it stands
+                let material = materials.add(themes.at(start_z + z).tunnel);
+                for side in [-1.0, 1.0] {
+                    parent.spawn((
+                        Mesh3d(post_mesh.clone()),
+                        MeshMaterial3d(material.clone()),
+                        Transform::from_xyz(side * span / 2.0, GANTRY_HEIGHT / 2.0, z),
+                    ));
+                }
+                parent.spawn((
+                    Mesh3d(beam_mesh.clone()),
+                    MeshMaterial3d(material),
+                    Transform::from_xyz(0.0, GANTRY_HEIGHT + 0.3, z),
+                ));
+            }
+        });
+}
+
+pub fn recycle_transition_gantries(
+    mut commands: Commands,
+    gantry_query: Query<(Entity, &TransitionGantries)>,
+    camera_query: Query<&Transform, With<Camera3d>>,
+) {
+    if let Ok(camera_transform) = camera_query.single() {
+        let cutoff = camera_transform.translation.z - 20.0;
+        for (entity, gantries) in gantry_query.iter() {
+            if gantries.end_z < cutoff {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+pub fn despawn_transition_gantries(
+    mut commands: Commands,
+    gantry_query: Query<Entity, With<TransitionGantries>>,
+) {
+    for entity in gantry_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
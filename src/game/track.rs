@@ -1,5 +1,11 @@
+use crate::game::path::OnPath;
+use crate::game::track_mesh::{SEGMENT_LENGTH, segment_mesh};
+use crate::resources::draw_horizon::DrawHorizon;
+use crate::resources::lane_layout::LaneLayout;
+use crate::resources::theme::{Theme, Themes};
 use crate::resources::track_pool::TrackPool;
 use bevy::prelude::*;
+use std::collections::HashMap;
 
 #[derive(Component)]
 pub struct TrackSegment {
@@ -10,82 +16,69 @@ pub struct TrackSegment {
 #[derive(Component)]
 pub struct Track;
 
-#[derive(Component)]
-pub struct Rail;
-
-#[derive(Component)]
-pub struct Sleeper;
+// Segments laid from just behind the camera when a run starts
+const INITIAL_SEGMENTS: std::ops::Range<i32> = -4..24;
+// Colour pairs kept before the mesh cache is cleared; meshes still on screen stay alive
+const MAX_CACHED_MESHES: usize = 64;
+
+/// Baked segment meshes shared by every segment painted alike, keyed by rail and
+/// sleeper colour, and the plain material they are all drawn with
+#[derive(Resource, Default)]
+pub struct TrackMeshes {
+    material: Option<Handle<StandardMaterial>>,
+    meshes: HashMap<[[u8; 4]; 2], Handle<Mesh>>,
+}
 
-pub const SEGMENT_LENGTH: f32 = 40.0;
-const RAIL_WIDTH: f32 = 0.1;
-const RAIL_HEIGHT: f32 = 0.15;
-const RAIL_SPACING: f32 = 0.4;
-const SLEEPER_WIDTH: f32 = 1.2;
-const SLEEPER_HEIGHT: f32 = 0.08;
-const SLEEPER_DEPTH: f32 = 0.2;
-const SLEEPER_SPACING: f32 = 2.0;
+impl TrackMeshes {
+    fn get_or_build(
+        &mut self,
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<StandardMaterial>,
+        theme: &Theme,
+    ) -> (Handle<Mesh>, Handle<StandardMaterial>) {
+        let material = self
+            .material
+            .get_or_insert_with(|| materials.add(StandardMaterial::default()))
+            .clone();
+
+        let key = [
+            theme.rail.to_srgba().to_u8_array(),
+            theme.sleeper.to_srgba().to_u8_array(),
+        ];
+        if !self.meshes.contains_key(&key) && self.meshes.len() >= MAX_CACHED_MESHES {
+            self.meshes.clear();
+        }
+        let mesh = self
+            .meshes
+            .entry(key)
+            .or_insert_with(|| meshes.add(segment_mesh(theme.rail, theme.sleeper)))
+            .clone();
+        (mesh, material)
+    }
+}
 
+/// Spawns one lane's segment of track as a single entity drawing the shared baked mesh
 pub fn spawn_track_segment(
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
-    track_index: u8,
-    segment_id: u32,
-    x_offset: f32,
-    z_position: f32,
+    track_meshes: &mut TrackMeshes,
+    segment: TrackSegment,
+    position: Vec3,
+    theme: &Theme,
 ) -> Entity {
-    // Rail mesh and material (metallic gray)
-    let rail_mesh = meshes.add(Cuboid::new(RAIL_WIDTH, RAIL_HEIGHT, SEGMENT_LENGTH));
-    let rail_material = materials.add(Color::srgb(0.4, 0.4, 0.45));
+    let (mesh, material) = track_meshes.get_or_build(meshes, materials, theme);
 
-    // Sleeper mesh and material (wooden brown)
-    let sleeper_mesh = meshes.add(Cuboid::new(SLEEPER_WIDTH, SLEEPER_HEIGHT, SLEEPER_DEPTH));
-    let sleeper_material = materials.add(Color::srgb(0.35, 0.2, 0.1));
-
-    let track_entity = commands
+    commands
         .spawn((
-            TrackSegment {
-                track_index,
-                segment_id,
-            },
+            segment,
             Track,
-            Transform::from_xyz(x_offset, 0.0, z_position),
-            Visibility::default(),
+            OnPath,
+            Mesh3d(mesh),
+            MeshMaterial3d(material),
+            Transform::from_translation(position),
         ))
-        .with_children(|parent| {
-            // Left rail
-            parent.spawn((
-                Rail,
-                Mesh3d(rail_mesh.clone()),
-                MeshMaterial3d(rail_material.clone()),
-                Transform::from_xyz(-RAIL_SPACING, RAIL_HEIGHT / 2.0 + SLEEPER_HEIGHT, 0.0),
-            ));
-
-            // Right rail
-            parent.spawn((
-                Rail,
-                Mesh3d(rail_mesh),
-                MeshMaterial3d(rail_material),
-                Transform::from_xyz(RAIL_SPACING, RAIL_HEIGHT / 2.0 + SLEEPER_HEIGHT, 0.0),
-            ));
-
-            // Sleepers
-            let num_sleepers = (SEGMENT_LENGTH / SLEEPER_SPACING) as i32;
-            let start_z = -SEGMENT_LENGTH / 2.0 + SLEEPER_SPACING / 2.0;
-
-            for i in 0..num_sleepers {
-                let z = start_z + (i as f32) * SLEEPER_SPACING;
-                parent.spawn((
-                    Sleeper,
-                    Mesh3d(sleeper_mesh.clone()),
-                    MeshMaterial3d(sleeper_material.clone()),
-                    Transform::from_xyz(0.0, SLEEPER_HEIGHT / 2.0, z),
-                ));
-            }
-        })
-        .id();
-
-    track_entity
+        .id()
 }
 
 pub fn generate_track_segments(
@@ -93,27 +86,51 @@ pub fn generate_track_segments(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut track_pool: ResMut<TrackPool>,
-    game_config: Res<crate::resources::game_config::GameConfig>,
+    mut track_meshes: ResMut<TrackMeshes>,
+    lane_layout: Res<LaneLayout>,
+    themes: Res<Themes>,
 ) {
-    let track_spacing = game_config.track_spacing;
+    lay_initial_tracks(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &mut track_pool,
+        &mut track_meshes,
+        &lane_layout,
+        &themes,
+    );
+}
 
+fn lay_initial_tracks(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    track_pool: &mut TrackPool,
+    track_meshes: &mut TrackMeshes,
+    lane_layout: &LaneLayout,
+    themes: &Themes,
+) {
     // Spawn multiple initial segments to ensure tracks are visible from the start
     // Start from behind the camera (z=-40) to ensure visibility
-    for segment_offset in -1..6 {
+    for segment_offset in INITIAL_SEGMENTS {
         let z_position = (segment_offset as f32) * SEGMENT_LENGTH;
+        let theme = themes.at(z_position);
         
-        for track_index in 0..3 {
-            let x_offset = (track_index as f32 - 1.0) * track_spacing;
+        for track_index in lane_layout.lanes() {
+            let x_offset = lane_layout.x_position(track_index);
             let segment_id = track_pool.active_segments.len() as u32;
 
             let track_entity = spawn_track_segment(
-                &mut commands,
-                &mut meshes,
-                &mut materials,
-                track_index,
-                segment_id,
-                x_offset,
-                z_position,
+                commands,
+                meshes,
+                materials,
+                track_meshes,
+                TrackSegment {
+                    track_index,
+                    segment_id,
+                },
+                Vec3::new(x_offset, 0.0, z_position),
+                &theme,
             );
 
             track_pool.active_segments.push(track_entity);
@@ -121,6 +138,34 @@ pub fn generate_track_segments(
     }
 }
 
+/// Lays fresh track for the next run
+pub fn reset_tracks(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut track_pool: ResMut<TrackPool>,
+    mut track_meshes: ResMut<TrackMeshes>,
+    track_query: Query<Entity, With<TrackSegment>>,
+    lane_layout: Res<LaneLayout>,
+    themes: Res<Themes>,
+) {
+    for entity in track_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    track_pool.active_segments.clear();
+    track_pool.available_segments.clear();
+
+    lay_initial_tracks(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &mut track_pool,
+        &mut track_meshes,
+        &lane_layout,
+        &themes,
+    );
+}
+
 pub fn recycle_track_segments(
     mut commands: Commands,
     mut track_pool: ResMut<TrackPool>,
@@ -147,13 +192,15 @@ pub fn extend_tracks_infinitely(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut track_pool: ResMut<TrackPool>,
+    mut track_meshes: ResMut<TrackMeshes>,
     track_query: Query<&Transform, (With<TrackSegment>, Without<Camera3d>)>,
     camera_query: Query<&Transform, (With<Camera3d>, Without<TrackSegment>)>,
-    game_config: Res<crate::resources::game_config::GameConfig>,
+    lane_layout: Res<LaneLayout>,
+    themes: Res<Themes>,
+    draw_horizon: Res<DrawHorizon>,
 ) {
     if let Ok(camera_transform) = camera_query.single() {
         let camera_z = camera_transform.translation.z;
-        let track_spacing = game_config.track_spacing;
 
         // Find the furthest track segment
         let mut max_z = camera_z;
@@ -166,10 +213,13 @@ pub fn extend_tracks_infinitely(
             }
         }
 
-        if max_z < camera_z + 100.0 {
-            for track_index in 0..3 {
-                let x_offset = (track_index as f32 - 1.0) * track_spacing;
-                let z_position = max_z + SEGMENT_LENGTH;
+        // Keep the far end of the track out past the fog
+        if max_z + SEGMENT_LENGTH / 2.0 < camera_z + draw_horizon.gameplay_distance() {
+            let z_position = max_z + SEGMENT_LENGTH;
+            let theme = themes.at(z_position);
+
+            for track_index in lane_layout.lanes() {
+                let x_offset = lane_layout.x_position(track_index);
 
                 let segment_id = track_pool.active_segments.len() as u32;
 
@@ -177,10 +227,13 @@ pub fn extend_tracks_infinitely(
                     &mut commands,
                     &mut meshes,
                     &mut materials,
-                    track_index,
-                    segment_id,
-                    x_offset,
-                    z_position,
+                    &mut track_meshes,
+                    TrackSegment {
+                        track_index,
+                        segment_id,
+                    },
+                    Vec3::new(x_offset, 0.0, z_position),
+                    &theme,
                 );
 
                 track_pool.active_segments.push(track_entity);
@@ -0,0 +1,57 @@
+use bevy::prelude::*;
+
+// Each segment bends onto the track path as one rigid piece around its centre, so its ends
+// stray from the curve by length² / (8 × radius). At the tightest 250 m bend a 10 m segment
+// strays 5 cm, half a rail's width. Before baking, 40 m segments were fine because their rails
+// were laid in 4 m pieces that bent separately; baked whole, they would stray 80 cm
+pub const SEGMENT_LENGTH: f32 = 10.0;
+pub const RAIL_WIDTH: f32 = 0.1;
+pub const RAIL_HEIGHT: f32 = 0.15;
+pub const RAIL_SPACING: f32 = 0.4;
+pub const SLEEPER_WIDTH: f32 = 1.2;
+pub const SLEEPER_HEIGHT: f32 = 0.08;
+pub const SLEEPER_DEPTH: f32 = 0.2;
+pub const SLEEPER_SPACING: f32 = 2.0;
+
+/// Cuboid moved into place within the segment and painted with a vertex colour
+fn painted_cuboid(size: Vec3, translation: Vec3, color: Color) -> Mesh {
+    let mesh = Cuboid::from_size(size).mesh().build();
+    let colors = vec![color.to_linear().to_f32_array(); mesh.count_vertices()];
+    mesh.with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+        .translated_by(translation)
+}
+
+/// Both rails and every sleeper of one lane segment baked into a single mesh, centred
+/// on the segment. Colours are carried per vertex, so one plain white material draws it.
+pub fn segment_mesh(rail: Color, sleeper: Color) -> Mesh {
+    let rail_size = Vec3::new(RAIL_WIDTH, RAIL_HEIGHT, SEGMENT_LENGTH);
+    let rail_y = RAIL_HEIGHT / 2.0 + SLEEPER_HEIGHT;
+    // The left rail starts the mesh and everything else is merged into it
+    let mut mesh = painted_cuboid(rail_size, Vec3::new(-RAIL_SPACING, rail_y, 0.0), rail);
+    let mut parts = vec![painted_cuboid(
+        rail_size,
+        Vec3::new(RAIL_SPACING, rail_y, 0.0),
+        rail,
+    )];
+
+    // Sleepers
+    let sleeper_size = Vec3::new(SLEEPER_WIDTH, SLEEPER_HEIGHT, SLEEPER_DEPTH);
+    let num_sleepers = (SEGMENT_LENGTH / SLEEPER_SPACING) as i32;
+    let start_z = -SEGMENT_LENGTH / 2.0 + SLEEPER_SPACING / 2.0;
+    for i in 0..num_sleepers {
+        let z = start_z + i as f32 * SLEEPER_SPACING;
+        parts.push(painted_cuboid(
+            sleeper_size,
+            Vec3::new(0.0, SLEEPER_HEIGHT / 2.0, z),
+            sleeper,
+        ));
+    }
+
+    for part in parts {
+        // Every part is a cuboid with the same attributes, so merging can't fail
+        if let Err(error) = mesh.merge(&part) {
+            warn!("Couldn't merge track mesh part: {error}");
+        }
+    }
+    mesh
+}
@@ -0,0 +1,209 @@
+use crate::game::path::OnPath;
+use crate::game::track_mesh::{RAIL_HEIGHT, RAIL_SPACING, RAIL_WIDTH, SLEEPER_HEIGHT};
+use crate::game::train::Train;
+use crate::resources::lane_layout::LaneLayout;
+use bevy::prelude::*;
+
+/// Crossover rails joining two lanes
+#[derive(Component)]
+pub struct TrackSwitch {
+    pub start_z: f32, // Far end of the crossover, used for recycling
+}
+
+/// A moving train that will cross over to another lane at a switch.
+///
+/// Trains run towards negative z, so they enter at `start_z` in lane `from` and
+/// leave at `end_z` in lane `to`.
+#[derive(Component)]
+pub struct LaneTransfer {
+    pub from: u8,
+    pub to: u8,
+    pub start_z: f32,
+    pub end_z: f32,
+}
+
+/// Blinking lamp on a train that is about to switch lanes
+#[derive(Component)]
+pub struct TurnSignal {
+    pub timer: Timer,
+}
+
+pub const SWITCH_LENGTH: f32 = 12.0;
+// Peak yaw of a train while it crosses over, in radians
+const TRANSFER_YAW: f32 = 0.12;
+
+/// Lays the crossover rails between the transfer's entry and exit points
+pub fn spawn_track_switch(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    transfer: &LaneTransfer,
+    lane_layout: &LaneLayout,
+) -> Entity {
+    let from_x = lane_layout.x_position(transfer.from);
+    let to_x = lane_layout.x_position(transfer.to);
+    let center = Vec3::new(
+        (from_x + to_x) / 2.0,
+        0.0,
+        (transfer.start_z + transfer.end_z) / 2.0,
+    );
+
+    // Local +z runs along the diagonal from the exit lane to the entry lane
+    let diagonal = Vec2::new(from_x - to_x, transfer.start_z - transfer.end_z);
+    let yaw = diagonal.x.atan2(diagonal.y);
+
+    let rail_mesh = meshes.add(Cuboid::new(RAIL_WIDTH, RAIL_HEIGHT, diagonal.length()));
+    let rail_material = materials.add(Color::srgb(0.55, 0.55, 0.6)); // Freshly polished steel
+    let frog_mesh = meshes.add(Cuboid::new(0.6, 0.04, 0.6));
+    let frog_material = materials.add(Color::srgb(0.9, 0.75, 0.1)); // Yellow switch plates
+
+    commands
+        .spawn((
+            TrackSwitch {
+                start_z: transfer.start_z,
+            },
+            OnPath,
+            Transform::from_translation(center).with_rotation(Quat::from_rotation_y(yaw)),
+            Visibility::default(),
+        ))
+        .with_children(|parent| {
+            for side in [-1.0, 1.0] {
+                parent.spawn((
+                    Mesh3d(rail_mesh.clone()),
+                    MeshMaterial3d(rail_material.clone()),
+                    Transform::from_xyz(
+                        side * RAIL_SPACING,
+                        RAIL_HEIGHT / 2.0 + SLEEPER_HEIGHT,
+                        0.0,
+                    ),
+                ));
+            }
+
+            // Plates where the crossover leaves and joins the lanes
+            for end in [-1.0, 1.0] {
+                parent.spawn((
+                    Mesh3d(frog_mesh.clone()),
+                    MeshMaterial3d(frog_material.clone()),
+                    Transform::from_xyz(0.0, SLEEPER_HEIGHT, end * diagonal.length() / 2.0),
+                ));
+            }
+        })
+        .id()
+}
+
+/// Puts blinking amber lamps on the leading face of a switching train, on the side it will move to
+pub fn telegraph_switch(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    train: Entity,
+    train_length: f32,
+    transfer: &LaneTransfer,
+) {
+    let side = if transfer.to > transfer.from {
+        1.0
+    } else {
+        -1.0
+    };
+    let lamp_mesh = meshes.add(Sphere::new(0.18));
+    let lamp_material = materials.add(StandardMaterial {
+        base_color: Color::srgb(1.0, 0.6, 0.0),
+        emissive: LinearRgba::rgb(8.0, 4.0, 0.0),
+        ..default()
+    });
+
+    for height in [1.4, 2.0] {
+        commands.spawn((
+            ChildOf(train),
+            TurnSignal {
+                timer: Timer::from_seconds(0.3, TimerMode::Repeating),
+            },
+            Mesh3d(lamp_mesh.clone()),
+            MeshMaterial3d(lamp_material.clone()),
+            Transform::from_xyz(side * 0.65, height, -train_length / 2.0 - 0.05),
+            Visibility::Visible,
+        ));
+    }
+}
+
+/// Slides switching trains across as their leading face runs over the crossover
+pub fn transfer_trains(
+    mut commands: Commands,
+    mut train_query: Query<(Entity, &mut Train, &mut Transform, &LaneTransfer)>,
+    lane_layout: Res<LaneLayout>,
+) {
+    for (entity, mut train, mut transform, transfer) in train_query.iter_mut() {
+        let leading_z = transform.translation.z - train.length / 2.0;
+        let progress =
+            ((transfer.start_z - leading_z) / (transfer.start_z - transfer.end_z)).clamp(0.0, 1.0);
+        let eased = progress * progress * (3.0 - 2.0 * progress);
+
+        let from_x = lane_layout.x_position(transfer.from);
+        let to_x = lane_layout.x_position(transfer.to);
+        transform.translation.x = from_x + (to_x - from_x) * eased;
+
+        // Nose into the turn; heading is towards -z, so moving to +x is a negative yaw
+        let direction = (to_x - from_x).signum();
+        let yaw = -direction * TRANSFER_YAW * (progress * std::f32::consts::PI).sin();
+        transform.rotation = Quat::from_rotation_y(yaw);
+
+        // Count as in the new lane once more than halfway over, for blocking and spawn checks
+        train.track_index = if progress >= 0.5 {
+            transfer.to
+        } else {
+            transfer.from
+        };
+
+        if progress >= 1.0 {
+            transform.rotation = Quat::IDENTITY;
+            commands.entity(entity).remove::<LaneTransfer>();
+        }
+    }
+}
+
+pub fn blink_turn_signals(
+    mut commands: Commands,
+    mut signal_query: Query<(Entity, &ChildOf, &mut TurnSignal, &mut Visibility)>,
+    transfer_query: Query<(), With<LaneTransfer>>,
+    time: Res<Time>,
+) {
+    for (entity, child_of, mut signal, mut visibility) in signal_query.iter_mut() {
+        // Lamps go out once the train is across
+        if transfer_query.get(child_of.parent()).is_err() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        signal.timer.tick(time.delta());
+        if signal.timer.just_finished() {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Visible,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+pub fn recycle_track_switches(
+    mut commands: Commands,
+    switch_query: Query<(Entity, &TrackSwitch)>,
+    camera_query: Query<&Transform, With<Camera3d>>,
+) {
+    if let Ok(camera_transform) = camera_query.single() {
+        let camera_z = camera_transform.translation.z;
+        for (entity, track_switch) in switch_query.iter() {
+            if track_switch.start_z < camera_z - 20.0 {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+pub fn despawn_track_switches(
+    mut commands: Commands,
+    switch_query: Query<Entity, With<TrackSwitch>>,
+) {
+    for entity in switch_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -1,35 +1,120 @@
+use crate::game::day_cycle::NightLight;
+use crate::game::path::OnPath;
 use crate::game::player::Player;
+use crate::game::track_switch::{
+    LaneTransfer, SWITCH_LENGTH, spawn_track_switch, telegraph_switch,
+};
+use crate::resources::draw_horizon::DrawHorizon;
 use crate::resources::game_config::GameConfig;
+use crate::resources::lane_layout::LaneLayout;
+use crate::resources::theme::{Theme, Themes};
+use crate::resources::track_path::{TrackPath, snap_to_grid};
 use avian3d::prelude::*;
 use bevy::prelude::*;
 
-#[derive(Component, Clone, Copy, PartialEq)]
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
 pub enum TrainType {
     Stationary,         // Just sits there, must go around
     StationaryWithRamp, // Has a ramp, can climb on top
     Moving,             // Moves along the track, must avoid
+    Express,            // Moves fast, so it arrives with little time to react
+}
+
+impl TrainType {
+    pub fn is_moving(self) -> bool {
+        matches!(self, TrainType::Moving | TrainType::Express)
+    }
+}
+
+/// What the spawner decided to build
+pub struct TrainSpec {
+    pub track_index: u8,
+    pub z_position: f32,
+    pub train_type: TrainType,
+    pub cars: u8,
+    pub speed: f32, // 0 for stationary trains
 }
 
 #[derive(Component)]
 pub struct Train {
     pub track_index: u8,
     pub train_type: TrainType,
-    pub length: f32, // Length of the train
-    pub speed: f32,  // Speed for moving trains (0 for stationary)
+    pub length: f32,     // Length of the whole train, couplers included
+    pub speed: f32,      // Speed for moving trains (0 for stationary)
+    pub velocity_z: f32, // How fast it actually moved this frame, for carrying riders
 }
 
 #[derive(Component)]
 pub struct TrainRamp;
 
+/// Marks the pieces of one car, counted from the back, so a single car can be smashed
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub struct TrainCar(pub u8);
+
+/// Cars a hoverboard has smashed through; they no longer block the player
+#[derive(Component, Clone, Default)]
+pub struct Smashed {
+    pub cars: Vec<u8>,
+}
+
 #[derive(Component)]
 pub struct TrainTop {
     pub height: f32,
 }
 
+pub const TRAIN_WIDTH: f32 = 1.6;
+const CAR_LENGTH: f32 = 8.0;
+const COUPLER_LENGTH: f32 = 0.6; // Gap between cars, bridged by the coupler
+const MAX_TRAIN_CARS: u8 = 4;
+// Percent of moving trains that change lanes at a switch
+const SWITCHING_TRAIN_CHANCE: u32 = 35;
+
+/// Length of a train with `cars` cars, couplers included
+pub fn train_length(cars: u8) -> f32 {
+    let cars = cars.max(1) as f32;
+    cars * CAR_LENGTH + (cars - 1.0) * COUPLER_LENGTH
+}
+
+/// Car covering the point `from_back` along the train from its back end
+pub fn car_at(train: &Train, from_back: f32) -> u8 {
+    let pitch = CAR_LENGTH + COUPLER_LENGTH;
+    let cars = ((train.length + COUPLER_LENGTH) / pitch).round() as u8;
+    ((from_back / pitch).max(0.0) as u8).min(cars.saturating_sub(1))
+}
+
+/// Picks how many cars a train gets; longer trains unlock as difficulty rises
+fn car_count(difficulty_scale: f32, seed: u32) -> u8 {
+    let max_cars = (1.0 + (difficulty_scale - 1.0) * 0.6).floor() as u8;
+    let max_cars = max_cars.clamp(1, MAX_TRAIN_CARS);
+    1 + (seed % max_cars as u32) as u8
+}
+
+/// Picks a speed from the spread for the train type, relative to the player's speed
+fn train_speed(train_type: TrainType, seed: u32, current_speed: f32) -> f32 {
+    let spread = (seed % 31) as f32 / 100.0;
+    match train_type {
+        TrainType::Moving => current_speed * (0.5 + spread), // Slower than the player
+        TrainType::Express => current_speed * (1.3 + spread),
+        _ => 0.0,
+    }
+}
+
+fn pick_train_type(seed: u32) -> TrainType {
+    match (seed / 3) % 10 {
+        0 | 1 => TrainType::StationaryWithRamp, // 20% chance - climbable
+        2..=5 => TrainType::Stationary,         // 40% chance - must avoid
+        6..=8 => TrainType::Moving,             // 30% chance - moving
+        _ => TrainType::Express,                // 10% chance - fast moving
+    }
+}
+
 /// Marker for train-related entities that should be recycled with trains
 #[derive(Component)]
 pub struct TrainPart;
 
+// Salt for train spawn keys
+const TRAIN_SALT: u32 = 0xBEEF_CAFE;
+
 // Simple pseudo-random for train spawning
 fn train_random(seed: u32) -> u32 {
     let mut x = seed;
@@ -43,43 +128,51 @@ pub fn spawn_train(
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
-    track_index: u8,
-    z_position: f32,
-    train_type: TrainType,
-    game_config: &GameConfig,
+    spec: TrainSpec,
+    lane_layout: &LaneLayout,
+    theme: &Theme,
 ) -> Entity {
-    let x_offset = (track_index as f32 - 1.0) * game_config.track_spacing;
-
-    // Train colors
-    let train_body_color = materials.add(Color::srgb(0.7, 0.1, 0.15)); // Dark red
-    let train_stripe_color = materials.add(Color::srgb(0.95, 0.85, 0.2)); // Yellow stripe
-    let train_window_color = materials.add(Color::srgb(0.2, 0.3, 0.4)); // Dark blue-gray windows
-    let train_roof_color = materials.add(Color::srgb(0.5, 0.5, 0.55)); // Gray roof
+    let TrainSpec {
+        track_index,
+        z_position,
+        train_type,
+        cars,
+        speed,
+    } = spec;
+    let x_offset = lane_layout.x_position(track_index);
+
+    // Train colors; livery comes from the theme, running gear stays the same everywhere
+    let train_body_color = match train_type {
+        TrainType::Express => materials.add(theme.express_body),
+        _ => materials.add(theme.train_body),
+    };
+    let train_stripe_color = materials.add(theme.train_stripe);
+    let train_window_color = materials.add(theme.train_window);
+    let train_roof_color = materials.add(theme.train_roof);
     let wheel_color = materials.add(Color::srgb(0.15, 0.15, 0.15)); // Black wheels
+    let coupler_color = materials.add(Color::srgb(0.25, 0.25, 0.28)); // Dark steel
     let ramp_color = materials.add(Color::srgb(0.6, 0.5, 0.3)); // Wooden ramp
 
     // Train dimensions
-    let train_length = 8.0;
-    let train_width = 1.6;
+    let cars = cars.clamp(1, MAX_TRAIN_CARS);
+    let train_length = train_length(cars);
+    let train_width = TRAIN_WIDTH;
     let train_height = 2.2;
     let train_y_base = train_height / 2.0;
 
     // Meshes
-    let body_mesh = meshes.add(Cuboid::new(train_width, train_height, train_length));
-    let roof_mesh = meshes.add(Cuboid::new(train_width - 0.1, 0.15, train_length - 0.2));
-    let stripe_mesh = meshes.add(Cuboid::new(train_width + 0.02, 0.2, train_length + 0.02));
+    let body_mesh = meshes.add(Cuboid::new(train_width, train_height, CAR_LENGTH));
+    let roof_mesh = meshes.add(Cuboid::new(train_width - 0.1, 0.15, CAR_LENGTH - 0.2));
+    let stripe_mesh = meshes.add(Cuboid::new(train_width + 0.02, 0.2, CAR_LENGTH + 0.02));
     let window_mesh = meshes.add(Cuboid::new(0.02, 0.5, 0.8));
     let wheel_mesh = meshes.add(Cylinder::new(0.25, 0.15));
-
-    let speed = match train_type {
-        TrainType::Moving => game_config.current_speed * 0.7, // Slightly slower than player
-        _ => 0.0,
-    };
+    let coupler_mesh = meshes.add(Cuboid::new(train_width - 0.4, 1.6, COUPLER_LENGTH + 0.1));
 
     // Use Kinematic for moving trains (so collider follows transform), Static for stationary
-    let rigid_body = match train_type {
-        TrainType::Moving => RigidBody::Kinematic,
-        _ => RigidBody::Static,
+    let rigid_body = if train_type.is_moving() {
+        RigidBody::Kinematic
+    } else {
+        RigidBody::Static
     };
 
     let mut train_entity = commands.spawn((
@@ -88,7 +181,9 @@ pub fn spawn_train(
             train_type,
             length: train_length,
             speed,
+            velocity_z: 0.0,
         },
+        OnPath,
         Transform::from_translation(Vec3::new(x_offset, 0.0, z_position)),
         Visibility::default(),
         // Add rigid body physics for physical collision
@@ -96,63 +191,132 @@ pub fn spawn_train(
     ));
 
     train_entity.with_children(|parent| {
-        // Train body collider (positioned at train body center)
-        parent.spawn((
-            Collider::cuboid(train_width / 2.0, train_height / 2.0, train_length / 2.0),
-            Transform::from_xyz(0.0, train_y_base, 0.0),
-        ));
+        // Cars run back to front; the first car (lowest z) faces the player
+        for car in 0..cars {
+            let car_z =
+                -train_length / 2.0 + CAR_LENGTH / 2.0 + car as f32 * (CAR_LENGTH + COUPLER_LENGTH);
+
+            // Body collider, reaching halfway across the couplers so they can't be slipped
+            // through
+            let half_pitch = (CAR_LENGTH + COUPLER_LENGTH) / 2.0;
+            let collider_back = if car == 0 {
+                car_z - CAR_LENGTH / 2.0
+            } else {
+                car_z - half_pitch
+            };
+            let collider_front = if car + 1 == cars {
+                car_z + CAR_LENGTH / 2.0
+            } else {
+                car_z + half_pitch
+            };
+            parent.spawn((
+                TrainCar(car),
+                Collider::cuboid(
+                    train_width / 2.0,
+                    train_height / 2.0,
+                    (collider_front - collider_back) / 2.0,
+                ),
+                Transform::from_xyz(0.0, train_y_base, (collider_back + collider_front) / 2.0),
+            ));
 
-        // Train top collider - full length platform on top for walking
-        parent.spawn((
-            Collider::cuboid(train_width / 2.0, 0.1, train_length / 2.0),
-            Transform::from_xyz(0.0, train_height + 0.1, 0.0),
-        ));
+            // Platform on the roof so the player can jump onto any train. Each car's meets
+            // the next one's over the coupler, so the roof runs the whole length until a
+            // car is smashed out of it
+            // Note: Collider::cuboid uses HALF-EXTENTS
+            let platform_width = train_width - 0.2;
+            let platform_height = 0.15;
+            let platform_length = collider_front - collider_back;
+            parent.spawn((
+                TrainCar(car),
+                TrainTop {
+                    height: train_height + 0.1,
+                },
+                Mesh3d(meshes.add(Cuboid::new(
+                    platform_width,
+                    platform_height,
+                    platform_length,
+                ))),
+                MeshMaterial3d(train_roof_color.clone()),
+                Transform::from_xyz(
+                    0.0,
+                    train_height + 0.025,
+                    (collider_back + collider_front) / 2.0,
+                ),
+                Collider::cuboid(
+                    platform_width / 2.0,
+                    platform_height / 2.0,
+                    platform_length / 2.0,
+                ),
+            ));
 
-        // Main body (visual)
-        parent.spawn((
-            Mesh3d(body_mesh.clone()),
-            MeshMaterial3d(train_body_color.clone()),
-            Transform::from_xyz(0.0, train_y_base, 0.0),
-        ));
+            // Main body (visual)
+            parent.spawn((
+                TrainCar(car),
+                Mesh3d(body_mesh.clone()),
+                MeshMaterial3d(train_body_color.clone()),
+                Transform::from_xyz(0.0, train_y_base, car_z),
+            ));
 
-        // Roof
-        parent.spawn((
-            Mesh3d(roof_mesh.clone()),
-            MeshMaterial3d(train_roof_color.clone()),
-            Transform::from_xyz(0.0, train_height + 0.075, 0.0),
-        ));
+            // Roof
+            parent.spawn((
+                TrainCar(car),
+                Mesh3d(roof_mesh.clone()),
+                MeshMaterial3d(train_roof_color.clone()),
+                Transform::from_xyz(0.0, train_height + 0.075, car_z),
+            ));
 
-        // Yellow stripe along the side
-        parent.spawn((
-            Mesh3d(stripe_mesh.clone()),
-            MeshMaterial3d(train_stripe_color.clone()),
-            Transform::from_xyz(0.0, train_y_base - 0.3, 0.0),
-        ));
+            // Yellow stripe along the side
+            parent.spawn((
+                TrainCar(car),
+                Mesh3d(stripe_mesh.clone()),
+                MeshMaterial3d(train_stripe_color.clone()),
+                Transform::from_xyz(0.0, train_y_base - 0.3, car_z),
+            ));
 
-        // Windows on both sides
-        for side in [-1.0, 1.0] {
-            for i in 0..4 {
-                let window_z = (i as f32 - 1.5) * 1.8;
-                parent.spawn((
-                    Mesh3d(window_mesh.clone()),
-                    MeshMaterial3d(train_window_color.clone()),
-                    Transform::from_xyz(
-                        side * (train_width / 2.0 + 0.01),
-                        train_y_base + 0.4,
-                        window_z,
-                    ),
-                ));
+            // Windows on both sides
+            for side in [-1.0, 1.0] {
+                for i in 0..4 {
+                    let window_z = car_z + (i as f32 - 1.5) * 1.8;
+                    parent.spawn((
+                        TrainCar(car),
+                        NightLight {
+                            emissive: theme.window_light.to_linear(),
+                        },
+                        Mesh3d(window_mesh.clone()),
+                        MeshMaterial3d(train_window_color.clone()),
+                        Transform::from_xyz(
+                            side * (train_width / 2.0 + 0.01),
+                            train_y_base + 0.4,
+                            window_z,
+                        ),
+                    ));
+                }
             }
-        }
 
-        // Wheels
-        for side in [-1.0, 1.0] {
-            for i in [-1.0, 1.0] {
+            // Wheels
+            for side in [-1.0, 1.0] {
+                for i in [-1.0, 1.0] {
+                    parent.spawn((
+                        TrainCar(car),
+                        Mesh3d(wheel_mesh.clone()),
+                        MeshMaterial3d(wheel_color.clone()),
+                        Transform::from_xyz(side * 0.6, 0.25, car_z + i * 2.5)
+                            .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2)),
+                    ));
+                }
+            }
+
+            // Coupler to the next car
+            if car + 1 < cars {
                 parent.spawn((
-                    Mesh3d(wheel_mesh.clone()),
-                    MeshMaterial3d(wheel_color.clone()),
-                    Transform::from_xyz(side * 0.6, 0.25, i * 2.5)
-                        .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2)),
+                    TrainCar(car),
+                    Mesh3d(coupler_mesh.clone()),
+                    MeshMaterial3d(coupler_color.clone()),
+                    Transform::from_xyz(
+                        0.0,
+                        train_y_base - 0.1,
+                        car_z + (CAR_LENGTH + COUPLER_LENGTH) / 2.0,
+                    ),
                 ));
             }
         }
@@ -161,7 +325,43 @@ pub fn spawn_train(
     // Get the train entity ID before spawning more entities
     let train_id = train_entity.id();
 
-    // Spawn ramp and platform as separate physics entities (not children)
+    // Headlights on the leading face of trains coming towards the player
+    if train_type.is_moving() {
+        let headlight_mesh = meshes.add(Sphere::new(0.15));
+        let headlight_color = materials.add(StandardMaterial {
+            base_color: Color::srgb(1.0, 0.95, 0.7),
+            emissive: LinearRgba::rgb(8.0, 7.0, 4.0),
+            ..default()
+        });
+        let front_z = -train_length / 2.0 - 0.05;
+
+        for side in [-1.0, 1.0] {
+            commands.spawn((
+                ChildOf(train_id),
+                Mesh3d(headlight_mesh.clone()),
+                MeshMaterial3d(headlight_color.clone()),
+                Transform::from_xyz(side * 0.5, 0.7, front_z),
+            ));
+        }
+        commands.spawn((
+            ChildOf(train_id),
+            SpotLight {
+                color: Color::srgb(1.0, 0.95, 0.7),
+                intensity: 2_000_000.0,
+                range: 40.0,
+                outer_angle: 0.5,
+                inner_angle: 0.3,
+                shadows_enabled: false,
+                ..default()
+            },
+            Transform::from_xyz(0.0, 1.0, front_z).looking_to(Vec3::NEG_Z, Vec3::Y),
+        ));
+    }
+
+    // The ramp and roof platform are children too, so they move with the train and are
+    // despawned with it. They carry no rigid body of their own: their colliders attach to
+    // the train's, so a kinematic train takes them along.
+    // The ramp always leans against the first car
     if train_type == TrainType::StationaryWithRamp {
         let ramp_length = 6.0;
         let ramp_width = 1.6;
@@ -172,7 +372,7 @@ pub fn spawn_train(
         let ramp_angle: f32 = (ramp_rise / ramp_length).atan(); // Calculate angle based on rise/run
 
         let ramp_center_y = ramp_rise / 2.0;
-        let ramp_center_z = z_position - train_length / 2.0 - (ramp_length / 2.0);
+        let ramp_center_z = -train_length / 2.0 - (ramp_length / 2.0);
 
         let ramp_mesh = meshes.add(Cuboid::new(ramp_width, ramp_thickness, ramp_length));
 
@@ -182,10 +382,11 @@ pub fn spawn_train(
 
         // Visual ramp mesh
         commands.spawn((
+            ChildOf(train_id),
             TrainRamp,
             Mesh3d(ramp_mesh),
             MeshMaterial3d(ramp_color.clone()),
-            Transform::from_xyz(x_offset, ramp_center_y, ramp_center_z)
+            Transform::from_xyz(0.0, ramp_center_y, ramp_center_z)
                 .with_rotation(Quat::from_rotation_x(-ramp_angle)),
         ));
 
@@ -193,7 +394,7 @@ pub fn spawn_train(
         let num_steps = 20;
         let step_length = ramp_length / num_steps as f32;
         let step_height = ramp_rise / num_steps as f32;
-        let ramp_start_z = z_position - train_length / 2.0 - ramp_length;
+        let ramp_start_z = -train_length / 2.0 - ramp_length;
 
         for i in 0..num_steps {
             let step_y = (i as f32 + 0.5) * step_height;
@@ -201,32 +402,33 @@ pub fn spawn_train(
 
             // Each step slightly overlaps with the next for smooth transition
             commands.spawn((
+                ChildOf(train_id),
                 TrainPart,
-                RigidBody::Static,
                 Collider::cuboid(
                     ramp_width / 2.0,
                     step_height / 2.0 + 0.05,
                     step_length / 2.0 + 0.1,
                 ),
-                Transform::from_xyz(x_offset, step_y, step_z),
+                Transform::from_xyz(0.0, step_y, step_z),
             ));
         }
 
         // Add a bridge collider connecting ramp top to train top platform
         commands.spawn((
+            ChildOf(train_id),
             TrainPart,
-            RigidBody::Static,
             Collider::cuboid(ramp_width / 2.0, 0.1, 0.5),
-            Transform::from_xyz(x_offset, ramp_rise, z_position - train_length / 2.0 - 0.25),
+            Transform::from_xyz(0.0, ramp_rise, -train_length / 2.0 - 0.25),
         ));
 
         // Left rail
         commands.spawn((
+            ChildOf(train_id),
             TrainPart,
             Mesh3d(rail_mesh.clone()),
             MeshMaterial3d(rail_color.clone()),
             Transform::from_xyz(
-                x_offset - (ramp_width / 2.0 + 0.04),
+                -(ramp_width / 2.0 + 0.04),
                 ramp_center_y + 0.12,
                 ramp_center_z,
             )
@@ -235,43 +437,15 @@ pub fn spawn_train(
 
         // Right rail
         commands.spawn((
+            ChildOf(train_id),
             TrainPart,
             Mesh3d(rail_mesh),
             MeshMaterial3d(rail_color),
-            Transform::from_xyz(
-                x_offset + (ramp_width / 2.0 + 0.04),
-                ramp_center_y + 0.12,
-                ramp_center_z,
-            )
-            .with_rotation(Quat::from_rotation_x(-ramp_angle)),
+            Transform::from_xyz(ramp_width / 2.0 + 0.04, ramp_center_y + 0.12, ramp_center_z)
+                .with_rotation(Quat::from_rotation_x(-ramp_angle)),
         ));
     }
 
-    // ALL trains get a platform on top so player can jump onto any train
-    // Note: Collider::cuboid uses HALF-EXTENTS
-    let platform_width = train_width - 0.2;
-    let platform_height = 0.15;
-    let platform_length = train_length; // Full length to avoid gaps
-    let top_platform_mesh = meshes.add(Cuboid::new(
-        platform_width,
-        platform_height,
-        platform_length,
-    ));
-    commands.spawn((
-        TrainTop {
-            height: train_height + 0.1,
-        },
-        Mesh3d(top_platform_mesh),
-        MeshMaterial3d(train_roof_color.clone()),
-        Transform::from_xyz(x_offset, train_height + 0.025, z_position),
-        RigidBody::Static,
-        Collider::cuboid(
-            platform_width / 2.0,
-            platform_height / 2.0,
-            platform_length / 2.0,
-        ),
-    ));
-
     train_id
 }
 
@@ -280,7 +454,11 @@ pub fn generate_trains_procedurally(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     game_config: Res<GameConfig>,
-    train_query: Query<&Transform, With<Train>>,
+    lane_layout: Res<LaneLayout>,
+    themes: Res<Themes>,
+    draw_horizon: Res<DrawHorizon>,
+    track_path: Res<TrackPath>,
+    train_query: Query<(&Transform, &Train)>,
     player_query: Query<&Transform, (With<Player>, Without<Train>)>,
 ) {
     if let Ok(player_transform) = player_query.single() {
@@ -294,85 +472,130 @@ pub fn generate_trains_procedurally(
         // Minimum spawn distance ahead of player
         let min_spawn_distance = 80.0;
         // Maximum spawn distance ahead
-        let max_spawn_distance = 150.0;
-        // Minimum gap between trains
-        let min_train_gap = 40.0;
-
-        // Find the furthest train
-        let mut max_train_z = player_z;
-        let mut train_count: u32 = 0;
-        for transform in train_query.iter() {
-            train_count += 1;
-            if transform.translation.z > max_train_z {
-                max_train_z = transform.translation.z;
+        let max_spawn_distance = draw_horizon.fill_distance(150.0);
+        // Minimum clear space between one train's front and the next train's back
+        let min_train_gap = 32.0;
+
+        // Find the furthest train front
+        let mut max_train_front = player_z;
+        for (transform, train) in train_query.iter() {
+            let front = transform.translation.z + train.length / 2.0;
+            if front > max_train_front {
+                max_train_front = front;
             }
         }
 
         // Only spawn if there's room and we need more trains ahead
         let spawn_threshold = player_z + min_spawn_distance;
 
-        if max_train_z < spawn_threshold {
-            let target_z = player_z + min_spawn_distance + 20.0;
-
-            let seed = train_random(
-                train_count
-                    .wrapping_add((target_z * 2.3) as u32)
-                    .wrapping_add(0xBEEFCAFE),
-            );
+        let back_z = if max_train_front < spawn_threshold {
+            snap_to_grid(player_z + min_spawn_distance + 16.0)
+        } else if max_train_front < player_z + max_spawn_distance {
+            let back_z = snap_to_grid(max_train_front + min_train_gap);
+            if back_z >= player_z + max_spawn_distance {
+                return;
+            }
+            back_z
+        } else {
+            return;
+        };
+        // Seeded from the track and the spot only, so the track seed picks the train
+        let seed = train_random(track_path.spawn_key(TRAIN_SALT, back_z));
+
+        let train_type = pick_train_type(seed);
+        let cars = car_count(game_config.difficulty_at(back_z), seed / 15);
+        let speed = train_speed(train_type, seed / 7, game_config.current_speed);
+
+        // Fast trains close the distance quicker, so they start further out to leave
+        // the same reaction time as anything else
+        let closing_speed = game_config.current_speed + speed;
+        let back_z = back_z.max(snap_to_grid(
+            player_z + closing_speed * game_config.train_reaction_time,
+        ));
 
-            // Pick a track (0, 1, or 2)
-            let track_index = (seed % 3) as u8;
+        let length = train_length(cars);
+
+        // Pick an open lane; it has to stay open under the whole train, and for a
+        // moving train all the way in past the player as well
+        let clear_from = if train_type.is_moving() {
+            player_z - 20.0
+        } else {
+            back_z
+        };
+        let clear_to = back_z + length;
+        let preferred = lane_layout.random_open_lane(seed, back_z);
+        let Some(track_index) = lane_layout.nearest_open_between(preferred, clear_from, clear_to)
+        else {
+            return;
+        };
 
-            // Determine train type
-            let train_type = match (seed / 3) % 5 {
-                0 => TrainType::StationaryWithRamp, // 20% chance - climbable
-                1 | 2 => TrainType::Stationary,     // 40% chance - must avoid
-                _ => TrainType::Moving,             // 40% chance - moving
+        let train = spawn_train(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            TrainSpec {
+                track_index,
+                z_position: back_z + length / 2.0,
+                train_type,
+                cars,
+                speed,
+            },
+            &lane_layout,
+            &themes.at(back_z),
+        );
+
+        // Some moving trains cross over to a neighbouring lane on the way in
+        let switch_roll = train_random(seed ^ 0x5EED5EED);
+        if train_type.is_moving()
+            && game_config.difficulty_at(back_z) > 1.3
+            && switch_roll % 100 < SWITCHING_TRAIN_CHANCE
+        {
+            let neighbours: Vec<u8> = [track_index.checked_sub(1), Some(track_index + 1)]
+                .into_iter()
+                .flatten()
+                .filter(|&lane| {
+                    lane < lane_layout.count
+                        && lane_layout.is_open_between(lane, clear_from, clear_to)
+                })
+                .collect();
+            if neighbours.is_empty() {
+                return;
+            }
+            let to = neighbours[(switch_roll / 2) as usize % neighbours.len()];
+
+            // Where the train would meet the player, then put the crossover halfway
+            // between that and the train so the move happens in plain view
+            let meeting_z =
+                player_z + (back_z - player_z) * game_config.current_speed / closing_speed;
+            let center_z = (meeting_z + back_z) / 2.0;
+            let transfer = LaneTransfer {
+                from: track_index,
+                to,
+                start_z: center_z + SWITCH_LENGTH / 2.0,
+                end_z: center_z - SWITCH_LENGTH / 2.0,
             };
 
-            spawn_train(
+            spawn_track_switch(
                 &mut commands,
                 &mut meshes,
                 &mut materials,
-                track_index,
-                target_z,
-                train_type,
-                &game_config,
+                &transfer,
+                &lane_layout,
             );
-        } else if max_train_z < player_z + max_spawn_distance {
-            let next_z = max_train_z + min_train_gap;
-
-            if next_z < player_z + max_spawn_distance {
-                let seed = train_random(
-                    train_count
-                        .wrapping_mul(23)
-                        .wrapping_add((next_z * 4.1) as u32)
-                        .wrapping_add(0xDEADFACE),
-                );
-
-                let track_index = (seed % 3) as u8;
-
-                let train_type = match (seed / 3) % 5 {
-                    0 => TrainType::StationaryWithRamp,
-                    1 | 2 => TrainType::Stationary,
-                    _ => TrainType::Moving,
-                };
-
-                spawn_train(
-                    &mut commands,
-                    &mut meshes,
-                    &mut materials,
-                    track_index,
-                    next_z,
-                    train_type,
-                    &game_config,
-                );
-            }
+            telegraph_switch(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                train,
+                length,
+                &transfer,
+            );
+            commands.entity(train).insert(transfer);
         }
     }
 }
 
-pub fn move_trains(mut train_query: Query<(Entity, &Train, &mut Transform)>, time: Res<Time>) {
+pub fn move_trains(mut train_query: Query<(Entity, &mut Train, &mut Transform)>, time: Res<Time>) {
     // First pass: collect all train positions
     let train_positions: Vec<(Entity, u8, f32, f32)> = train_query
         .iter()
@@ -387,8 +610,8 @@ pub fn move_trains(mut train_query: Query<(Entity, &Train, &mut Transform)>, tim
         .collect();
 
     // Second pass: move trains that aren't blocked
-    for (entity, train, mut transform) in train_query.iter_mut() {
-        if train.train_type == TrainType::Moving {
+    for (entity, mut train, mut transform) in train_query.iter_mut() {
+        if train.train_type.is_moving() {
             let my_track = train.track_index;
             let my_z = transform.translation.z;
             let my_back = my_z - train.length / 2.0;
@@ -417,9 +640,26 @@ pub fn move_trains(mut train_query: Query<(Entity, &Train, &mut Transform)>, tim
                 }
             }
 
-            if !blocked {
-                // Moving trains come towards the player (negative Z direction)
-                transform.translation.z -= train.speed * time.delta_secs();
+            // Moving trains come towards the player (negative Z direction)
+            train.velocity_z = if blocked { 0.0 } else { -train.speed };
+            transform.translation.z += train.velocity_z * time.delta_secs();
+        }
+    }
+}
+
+/// Takes smashed cars out of their trains, roof included, leaving the other cars and the ramp
+pub fn clear_smashed_cars(
+    mut commands: Commands,
+    train_query: Query<(&Smashed, &Children), Changed<Smashed>>,
+    car_query: Query<&TrainCar>,
+) {
+    for (smashed, children) in train_query.iter() {
+        for child in children.iter() {
+            if car_query
+                .get(child)
+                .is_ok_and(|car| smashed.cars.contains(&car.0))
+            {
+                commands.entity(child).despawn();
             }
         }
     }
@@ -427,56 +667,16 @@ pub fn move_trains(mut train_query: Query<(Entity, &Train, &mut Transform)>, tim
 
 pub fn recycle_trains(
     mut commands: Commands,
-    train_query: Query<(Entity, &Transform), With<Train>>,
-    ramp_query: Query<(Entity, &Transform), (With<TrainRamp>, Without<Train>)>,
-    top_query: Query<(Entity, &Transform), (With<TrainTop>, Without<Train>, Without<TrainRamp>)>,
-    parts_query: Query<
-        (Entity, &Transform),
-        (
-            With<TrainPart>,
-            Without<Train>,
-            Without<TrainRamp>,
-            Without<TrainTop>,
-        ),
-    >,
-    camera_query: Query<
-        &Transform,
-        (
-            With<Camera3d>,
-            Without<Train>,
-            Without<TrainRamp>,
-            Without<TrainTop>,
-            Without<TrainPart>,
-        ),
-    >,
+    train_query: Query<(Entity, &Transform, &Train)>,
+    camera_query: Query<&Transform, (With<Camera3d>, Without<Train>)>,
 ) {
     if let Ok(camera_transform) = camera_query.single() {
         let camera_z = camera_transform.translation.z;
 
-        // Recycle trains
-        for (entity, transform) in train_query.iter() {
-            if transform.translation.z < camera_z - 30.0 {
-                commands.entity(entity).despawn();
-            }
-        }
-
-        // Recycle ramps
-        for (entity, transform) in ramp_query.iter() {
-            if transform.translation.z < camera_z - 35.0 {
-                commands.entity(entity).despawn();
-            }
-        }
-
-        // Recycle train tops
-        for (entity, transform) in top_query.iter() {
-            if transform.translation.z < camera_z - 30.0 {
-                commands.entity(entity).despawn();
-            }
-        }
-
-        // Recycle train parts (step colliders, rails, etc.)
-        for (entity, transform) in parts_query.iter() {
-            if transform.translation.z < camera_z - 35.0 {
+        // Recycle trains once their front end is behind the camera; the roof, ramp and
+        // step colliders are children and go with them
+        for (entity, transform, train) in train_query.iter() {
+            if transform.translation.z + train.length / 2.0 < camera_z - 26.0 {
                 commands.entity(entity).despawn();
             }
         }
@@ -1,63 +1,208 @@
 use avian3d::prelude::*;
+use bevy::audio::AddAudioSource;
+use bevy::camera::visibility::VisibilitySystems;
+use bevy::light::SimulationLightSystems;
 use bevy::prelude::*;
 
 mod game;
 mod resources;
 
+use game::action_map::{ActionState, load_action_map, update_action_state};
+use game::audio::{SoundCue, Synth};
 use game::camera::{follow_player, handle_viewport_resize, setup_camera};
-use game::game_state::GameState;
-use game::input::{PlayerInput, handle_keyboard_input};
-use game::props::recycle_props;
-use game::systems::{
-    accelerate_speed, move_player_forward, setup_player, setup_props, setup_tracks,
+use game::collision::DeathCause;
+use game::events::{
+    CoinCollected, Jumped, Landed, LaneChanged, NearMiss, ObstacleCleared, PlayerDied, Slid,
+    Stumbled,
 };
-use game::track::{extend_tracks_infinitely, recycle_track_segments};
+use game::game_state::GameState;
+use game::gestures::{SwipeRecognizer, recognize_gestures};
+use game::input::{InputBuffer, PlayerInput, buffer_player_input, update_player_input};
+use game::missions::{AchievementGallery, GoalProgress, MissionNotice, RunTally, gallery_closed};
+use game::music::{MusicBank, MusicMix, Stem};
+use game::path::StraightTransforms;
+use game::props::{recycle_props, stream_props};
+use game::replay::ReplayBuffer;
+use game::settings::{SettingsMenu, settings_closed};
+use game::signal::TrainHorn;
+use game::systems::{accelerate_speed, move_player_forward, setup_player, setup_tracks};
+use game::track::{TrackMeshes, extend_tracks_infinitely, recycle_track_segments};
+use resources::daily::DailyRun;
+use resources::day_cycle::DayCycle;
+use resources::draw_horizon::DrawHorizon;
 use resources::game_config::GameConfig;
+use resources::lane_layout::LaneLayout;
+use resources::missions::MissionBook;
+use resources::profile::PlayerProfile;
 use resources::score::Score;
+use resources::theme::Themes;
+use resources::track_path::TrackPath;
 use resources::track_pool::TrackPool;
 
 fn main() {
     App::new()
         .add_plugins((DefaultPlugins, PhysicsPlugins::default()))
+        .add_audio_source::<Synth>()
+        .add_audio_source::<Stem>()
         .init_state::<GameState>()
         .init_resource::<GameConfig>()
         .init_resource::<TrackPool>()
+        .init_resource::<TrackMeshes>()
+        .init_resource::<LaneLayout>()
+        .init_resource::<TrackPath>()
+        .init_resource::<Themes>()
+        .init_resource::<DayCycle>()
+        .init_resource::<DrawHorizon>()
+        .init_resource::<StraightTransforms>()
         .init_resource::<PlayerInput>()
+        .init_resource::<InputBuffer>()
         .init_resource::<Score>()
+        .init_resource::<PlayerProfile>()
+        .init_resource::<ActionState>()
+        .init_resource::<SettingsMenu>()
+        .init_resource::<SwipeRecognizer>()
+        .init_resource::<MusicMix>()
+        .init_resource::<MusicBank>()
+        .init_resource::<DeathCause>()
+        .init_resource::<ReplayBuffer>()
+        .init_resource::<MissionBook>()
+        .init_resource::<RunTally>()
+        .init_resource::<AchievementGallery>()
+        .init_resource::<DailyRun>()
+        .add_message::<TrainHorn>()
+        .add_message::<CoinCollected>()
+        .add_message::<ObstacleCleared>()
+        .add_message::<NearMiss>()
+        .add_message::<LaneChanged>()
+        .add_message::<Jumped>()
+        .add_message::<Slid>()
+        .add_message::<Landed>()
+        .add_message::<Stumbled>()
+        .add_message::<PlayerDied>()
+        .add_message::<SoundCue>()
+        .add_message::<GoalProgress>()
+        .add_message::<MissionNotice>()
         .add_systems(
             Startup,
             (
+                load_action_map,
+                game::audio::load_sound_bank,
                 setup_camera,
                 setup_tracks,
-                setup_props,
                 setup_player,
                 game::systems::setup_coin_ui,
+                game::hoverboard::setup_hoverboard_ui,
+                game::missions::setup_mission_popups,
+                game::missions::reset_run_tally,
             ),
         )
         .add_systems(
             Update,
             (
-                handle_keyboard_input,
+                (
+                    update_action_state,
+                    recognize_gestures,
+                    update_player_input,
+                    buffer_player_input,
+                )
+                    .chain(),
                 game::systems::show_game_over_on_state_change,
-                game::systems::handle_game_over_restart,
+                game::systems::handle_game_over_restart
+                    .after(recognize_gestures)
+                    .run_if(settings_closed)
+                    .run_if(gallery_closed),
                 game::systems::update_coin_ui,
+                game::hoverboard::update_hoverboard_ui,
                 handle_viewport_resize,
+                (
+                    game::day_cycle::advance_day_cycle,
+                    game::theme::apply_theme_lighting,
+                    game::day_cycle::light_night_lamps,
+                )
+                    .chain(),
+                (
+                    (
+                        game::audio::cue_gameplay_sounds,
+                        game::audio::cue_power_ups,
+                        game::audio::cue_train_horns,
+                    ),
+                    (
+                        game::audio::play_sound_cues,
+                        (game::music::update_music_mix, game::music::play_music).chain(),
+                        game::music::play_game_over_sting,
+                    ),
+                )
+                    .chain(),
+                game::audio::attach_train_rumble,
+                game::audio::apply_volume_levels,
+                (
+                    (
+                        game::missions::tally_gameplay_events,
+                        game::missions::tally_distance.run_if(in_state(GameState::Playing)),
+                    ),
+                    game::missions::advance_missions,
+                    game::missions::show_mission_popups,
+                )
+                    .chain(),
+                game::missions::expire_mission_popups,
+            ),
+        )
+        .add_systems(
+            OnEnter(GameState::GameOver),
+            (
+                game::shop::bank_run_coins,
+                game::shop::show_shop_ui,
+                game::hoverboard::clear_hoverboard_on_game_over,
+                game::replay::start_replay,
+                game::daily::record_daily_score,
             ),
         )
+        .add_systems(
+            Update,
+            (
+                game::shop::handle_shop_purchase
+                    .run_if(settings_closed)
+                    .run_if(gallery_closed),
+                game::shop::update_shop_ui,
+                game::settings::handle_settings_menu.run_if(gallery_closed),
+                game::settings::update_settings_ui,
+                game::missions::handle_achievement_gallery.run_if(settings_closed),
+                game::missions::update_gallery_ui,
+                // Before a restart puts the player back, so it can't be undone the same frame
+                game::replay::play_crash_replay.before(game::systems::handle_game_over_restart),
+            )
+                .after(recognize_gestures)
+                .run_if(in_state(GameState::GameOver)),
+        )
         .add_systems(
             Update,
             (
                 move_player_forward,
+                game::systems::update_run_distance,
                 accelerate_speed,
-                game::systems::handle_track_switching,
-                game::systems::handle_slide_jump_input,
+                (
+                    game::systems::handle_track_switching,
+                    game::systems::force_out_of_closed_lanes,
+                )
+                    .chain(),
+                game::systems::tween_lane_switch,
+                (
+                    game::systems::update_ground_contact,
+                    game::systems::handle_slide_jump_input,
+                )
+                    .chain(),
                 game::systems::handle_slide_timer,
                 game::systems::handle_animations,
                 game::systems::animate_player_limbs,
-                game::systems::detect_collisions,
-                game::systems::detect_train_collisions,
+                (
+                    game::systems::detect_collisions,
+                    game::systems::detect_train_collisions,
+                    game::systems::detect_passed_obstacles,
+                    game::systems::end_run_on_death,
+                )
+                    .chain(),
+                game::train::clear_smashed_cars.after(game::systems::detect_train_collisions),
                 game::barricade::generate_obstacles_procedurally,
-                game::barricade::scale_difficulty,
                 game::barricade::recycle_barricades,
             )
                 .run_if(in_state(GameState::Playing)),
@@ -66,17 +211,70 @@ fn main() {
             Update,
             (
                 game::train::generate_trains_procedurally,
-                game::train::move_trains,
+                (
+                    game::train::move_trains,
+                    game::track_switch::transfer_trains,
+                )
+                    .chain(),
+                game::track_switch::blink_turn_signals,
+                game::track_switch::recycle_track_switches,
+                game::signal::warn_approaching_trains,
+                game::signal::flash_lane_signals,
                 game::train::recycle_trains,
                 game::coin::generate_coins_procedurally,
-                game::coin::collect_coins,
+                (game::coin::collect_coins, game::coin::count_collected_coins).chain(),
                 game::coin::recycle_coins,
                 extend_tracks_infinitely,
                 recycle_track_segments,
+                stream_props,
                 recycle_props,
                 follow_player,
             )
                 .run_if(in_state(GameState::Playing)),
         )
+        .add_systems(
+            Update,
+            (
+                game::hoverboard::activate_hoverboard.run_if(game::daily::power_ups_allowed),
+                game::hoverboard::tick_hoverboard,
+                game::hoverboard::blink_invulnerable,
+                game::chaser::tick_stumble,
+                game::chaser::spawn_chaser_on_stumble,
+                game::chaser::follow_player_chaser,
+                game::bridge::generate_bridges,
+                game::bridge::recycle_bridges,
+                game::theme::spawn_transition_gantries,
+                game::theme::recycle_transition_gantries,
+                game::replay::record_replay,
+            )
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            OnExit(GameState::GameOver),
+            (
+                game::chaser::despawn_chasers,
+                game::input::clear_input_buffer,
+                game::settings::close_settings_menu,
+                game::track_switch::despawn_track_switches,
+                game::bridge::despawn_bridges,
+                game::path::reset_track_path,
+                game::props::despawn_props,
+                game::track::reset_tracks,
+                game::theme::despawn_transition_gantries,
+                game::replay::clear_replay,
+                game::missions::close_achievement_gallery,
+                game::missions::reset_run_tally,
+            ),
+        )
+        .add_systems(First, game::path::restore_straight_transforms)
+        .add_systems(
+            PostUpdate,
+            (game::path::extend_track_path, game::path::bend_onto_path)
+                .chain()
+                .after(TransformSystems::Propagate)
+                .before(VisibilitySystems::CalculateBounds)
+                .before(VisibilitySystems::UpdateFrusta)
+                .before(SimulationLightSystems::UpdateDirectionalLightCascades),
+        )
         .run();
 }
@@ -0,0 +1,245 @@
+use crate::resources::game_config::GameConfig;
+use crate::resources::profile::PlayerProfile;
+use bevy::prelude::*;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Environment variable that sets the daily run's date, as `YYYY-MM-DD`, instead of today
+const DATE_ENV: &str = "SUBWAY_SURF_DATE";
+
+/// A calendar day, which picks the daily run's seed and modifiers
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// Today's local date, unless `SUBWAY_SURF_DATE` gives another
+    pub fn today() -> Self {
+        if let Some(date) = std::env::var(DATE_ENV)
+            .ok()
+            .and_then(|text| Self::parse(&text))
+        {
+            return date;
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() as i64);
+        Self::from_days((now + utc_offset(now)).div_euclid(86_400))
+    }
+
+    /// Date `days` days after 1970-01-01, by the proleptic Gregorian calendar
+    fn from_days(days: i64) -> Self {
+        // Counted in 400 year eras starting on 0000-03-01, so leap days fall at era ends
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153; // March is 0
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = (if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        }) as u32;
+        let year = (year_of_era + era * 400 + (month <= 2) as i64) as i32;
+        Self { year, month, day }
+    }
+
+    /// Reads `YYYY-MM-DD`, rejecting days the month doesn't have
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.trim().splitn(3, '-');
+        let year = parts.next()?.parse().ok()?;
+        let month = parts.next()?.parse().ok()?;
+        let day = parts.next()?.parse().ok()?;
+        ((1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day))
+            .then_some(Self { year, month, day })
+    }
+
+    /// Track seed shared by everyone playing this day's run
+    pub fn seed(&self) -> u32 {
+        let mut x = (self.year as u32)
+            .wrapping_mul(372)
+            .wrapping_add(self.month * 31 + self.day)
+            .wrapping_mul(0x9E37_79B9)
+            ^ 0xDA1E_5EED;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        x
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Seconds the local time zone is ahead of UTC at `now`
+#[cfg(unix)]
+fn utc_offset(now: i64) -> i64 {
+    let time = now as libc::time_t;
+    // SAFETY: `tm` is plain data, and both pointers are valid for the duration of the call
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return 0;
+    }
+    tm.tm_gmtoff as i64
+}
+
+// Elsewhere the day changes over at midnight UTC
+#[cfg(not(unix))]
+fn utc_offset(_now: i64) -> i64 {
+    0
+}
+
+/// A rule that changes how the daily run plays
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DailyModifier {
+    FasterStart,      // Starts at `daily_start_speed` times the base speed
+    NoPowerUps,       // Hoverboards can't be used
+    FastAcceleration, // Speeds up `daily_acceleration` times as quickly
+}
+
+impl DailyModifier {
+    pub const ALL: [DailyModifier; 3] = [
+        DailyModifier::FasterStart,
+        DailyModifier::NoPowerUps,
+        DailyModifier::FastAcceleration,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DailyModifier::FasterStart => "Faster start",
+            DailyModifier::NoPowerUps => "No power-ups",
+            DailyModifier::FastAcceleration => "Fast acceleration",
+        }
+    }
+}
+
+/// The day's challenge: a run on a track seeded by the date, with that day's modifiers
+#[derive(Resource)]
+pub struct DailyRun {
+    pub active: bool, // Whether the current or last run is the daily one
+    pub date: Date,
+    pub modifiers: Vec<DailyModifier>,
+}
+
+impl Default for DailyRun {
+    fn default() -> Self {
+        let mut daily_run = Self {
+            active: false,
+            date: Date::today(),
+            modifiers: Vec::new(),
+        };
+        daily_run.pick_modifiers();
+        daily_run
+    }
+}
+
+impl DailyRun {
+    /// Starts the daily run, picking up the date in case the day has changed since launch
+    pub fn begin(&mut self) {
+        self.active = true;
+        self.date = Date::today();
+        self.pick_modifiers();
+    }
+
+    /// At least one modifier each day, chosen by the seed's low bits
+    fn pick_modifiers(&mut self) {
+        let seed = self.date.seed();
+        self.modifiers = DailyModifier::ALL
+            .into_iter()
+            .enumerate()
+            .filter(|(bit, _)| seed & (1 << bit) != 0)
+            .map(|(_, modifier)| modifier)
+            .collect();
+        if self.modifiers.is_empty() {
+            let index = (seed >> 8) as usize % DailyModifier::ALL.len();
+            self.modifiers.push(DailyModifier::ALL[index]);
+        }
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.date.seed()
+    }
+
+    /// Whether `modifier` is in play for the current run
+    pub fn has(&self, modifier: DailyModifier) -> bool {
+        self.active && self.modifiers.contains(&modifier)
+    }
+
+    pub fn start_speed(&self, game_config: &GameConfig) -> f32 {
+        if self.has(DailyModifier::FasterStart) {
+            game_config.base_speed * game_config.daily_start_speed
+        } else {
+            game_config.base_speed
+        }
+    }
+
+    pub fn acceleration_factor(&self, game_config: &GameConfig) -> f32 {
+        if self.has(DailyModifier::FastAcceleration) {
+            game_config.daily_acceleration
+        } else {
+            1.0
+        }
+    }
+
+    /// Daily runs are scored without the mission multiplier, so everyone's scores compare
+    pub fn multiplier(&self, profile: &PlayerProfile) -> u32 {
+        if self.active { 1 } else { profile.multiplier() }
+    }
+
+    /// Result to paste to others, e.g. `subway-daily/2026-10-19/5f3a9c21/12345/1234m`
+    pub fn share_string(&self, score: u32, distance: f32) -> String {
+        format!(
+            "subway-daily/{}/{:08x}/{score}/{}m",
+            self.date,
+            self.seed(),
+            distance as u32
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rejects_days_the_month_does_not_have() {
+        assert_eq!(
+            Date::parse("2026-10-19"),
+            Some(Date {
+                year: 2026,
+                month: 10,
+                day: 19
+            })
+        );
+        assert_eq!(Date::parse("2026-02-31"), None);
+        assert_eq!(Date::parse("2026-04-31"), None);
+        assert_eq!(Date::parse("2026-13-01"), None);
+        assert_eq!(Date::parse("2026-01-00"), None);
+    }
+
+    #[test]
+    fn parse_accepts_leap_days_only_in_leap_years() {
+        assert!(Date::parse("2028-02-29").is_some());
+        assert!(Date::parse("2000-02-29").is_some());
+        assert!(Date::parse("2026-02-29").is_none());
+        assert!(Date::parse("2100-02-29").is_none());
+    }
+}
@@ -0,0 +1,69 @@
+use crate::resources::game_config::GameConfig;
+use bevy::prelude::*;
+use std::f32::consts::TAU;
+
+// Runs start somewhere between mid-morning and late afternoon
+const START_PHASE: f32 = 0.3;
+const START_PHASE_SPREAD: u32 = 350;
+
+/// Time of day, tied to distance along the run.
+///
+/// The phase goes 0.0 at midnight, 0.25 at sunrise, 0.5 at noon and 0.75 at sunset.
+/// Where a run starts in the day comes from the track path seed, so the same seed
+/// always lights the same stretch the same way.
+#[derive(Resource)]
+pub struct DayCycle {
+    phase: f32,
+}
+
+impl Default for DayCycle {
+    fn default() -> Self {
+        Self { phase: 0.5 }
+    }
+}
+
+// Simple pseudo-random for the starting time of day
+fn day_random(seed: u32) -> u32 {
+    let mut x = seed ^ 0xDA7_C1C1;
+    x ^= x << 13;
+    x ^= x >> 17;
+    x ^= x << 5;
+    x
+}
+
+impl DayCycle {
+    pub fn at(distance: f32, seed: u32, game_config: &GameConfig) -> Self {
+        let start = START_PHASE + (day_random(seed) % START_PHASE_SPREAD) as f32 / 1000.0;
+        if game_config.day_length <= 0.0 {
+            return Self { phase: start };
+        }
+        Self {
+            phase: (start + distance.max(0.0) / game_config.day_length).fract(),
+        }
+    }
+
+    /// Height of the sun, from -1.0 at midnight to 1.0 at noon
+    pub fn sun_elevation(&self) -> f32 {
+        ((self.phase - 0.25) * TAU).sin()
+    }
+
+    /// 1.0 in full daylight, 0.0 at night, easing through dawn and dusk
+    pub fn daylight(&self) -> f32 {
+        let t = ((self.sun_elevation() + 0.1) / 0.3).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    /// 1.0 with the sun on the horizon, fading to 0.0 as it rises or sets further
+    pub fn twilight(&self) -> f32 {
+        (1.0 - self.sun_elevation().abs() / 0.3).clamp(0.0, 1.0)
+    }
+
+    /// Orientation of the directional light: the sun by day, the moon by night
+    pub fn sun_rotation(&self) -> Quat {
+        let elevation = self.sun_elevation();
+        // Turns a full circle a day, so the moon rises where the sun set
+        let azimuth = (self.phase - 0.5) * TAU;
+        let pitch = -(0.15 + elevation.abs());
+        Quat::from_rotation_y(azimuth) * Quat::from_rotation_x(pitch)
+    }
+}
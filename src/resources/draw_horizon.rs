@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+
+/// Environment variable that picks the graphics preset
+const GRAPHICS_PRESET_ENV: &str = "SUBWAY_SURF_GRAPHICS";
+
+/// How far ahead the world is built and drawn
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GraphicsPreset {
+    Low,
+    Medium,
+    High,
+}
+
+impl GraphicsPreset {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "low" => Some(GraphicsPreset::Low),
+            "medium" => Some(GraphicsPreset::Medium),
+            "high" => Some(GraphicsPreset::High),
+            _ => None,
+        }
+    }
+
+    fn horizon(self) -> f32 {
+        match self {
+            GraphicsPreset::Low => 100.0,
+            GraphicsPreset::Medium => 180.0,
+            GraphicsPreset::High => 300.0,
+        }
+    }
+}
+
+/// Furthest ahead that a gameplay spawner fills (trains), which fog must never hide
+const GAMEPLAY_REACH: f32 = 150.0;
+/// Fraction of the drawn distance at which the fog hides everything
+const FOG_END_FRACTION: f32 = 0.9;
+
+/// Distance past the camera that decorations are spawned out to, with fog closing in
+/// before it so nothing is seen popping in.
+///
+/// The track and everything on it are built at least out to `gameplay_distance`, and
+/// the fog never closes in before a train comes into reach, so a low preset only thins
+/// out the scenery; it never gives less warning.
+#[derive(Resource)]
+pub struct DrawHorizon {
+    pub distance: f32,
+}
+
+impl Default for DrawHorizon {
+    fn default() -> Self {
+        let preset = std::env::var(GRAPHICS_PRESET_ENV)
+            .ok()
+            .and_then(|value| GraphicsPreset::from_name(&value))
+            .unwrap_or(GraphicsPreset::Medium);
+        Self::new(preset)
+    }
+}
+
+impl DrawHorizon {
+    pub fn new(preset: GraphicsPreset) -> Self {
+        Self {
+            distance: preset.horizon(),
+        }
+    }
+
+    /// How far ahead a gameplay spawner fills: the horizon, but never short of `fair_distance`
+    pub fn fill_distance(&self, fair_distance: f32) -> f32 {
+        self.distance.max(fair_distance)
+    }
+
+    /// How far the track and bridges are built: the horizon, but never short of where
+    /// the fog ends
+    pub fn gameplay_distance(&self) -> f32 {
+        self.fill_distance(GAMEPLAY_REACH / FOG_END_FRACTION)
+    }
+
+    /// Where the fog starts to thicken
+    pub fn fog_start(&self) -> f32 {
+        self.gameplay_distance() * 0.5
+    }
+
+    /// Where the fog hides everything, just short of the horizon and never before a
+    /// newly spawned train
+    pub fn fog_end(&self) -> f32 {
+        self.gameplay_distance() * FOG_END_FRACTION
+    }
+}
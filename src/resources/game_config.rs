@@ -6,9 +6,37 @@ pub struct GameConfig {
     pub speed_acceleration_rate: f32,
     pub current_speed: f32,
     pub barricade_spawn_base_interval: f32,
-    pub difficulty_scale: f32,
-    pub track_spacing: f32,
+    pub difficulty_distance: f32,
     pub barricade_advance_time: f32,
+    pub hoverboard_duration: f32,
+    pub hoverboard_price: u32,
+    pub invulnerability_duration: f32,
+    pub stumble_window: f32,
+    pub stumble_slowdown: f32,
+    pub stumble_recovery: f32,
+    pub chaser_gap: f32,
+    pub lane_switch_duration: f32,
+    pub lane_switch_lean: f32,
+    pub input_buffer_window: f32,
+    pub coyote_time: f32,
+    pub fast_fall_speed: f32,
+    pub landing_duration: f32,
+    pub slide_min_duration: f32,
+    pub slide_max_duration: f32,
+    pub swipe_min_distance: f32,
+    pub swipe_min_velocity: f32,
+    pub tap_max_distance: f32,
+    pub double_tap_window: f32,
+    pub train_reaction_time: f32,
+    pub train_warning_time: f32,
+    pub path_max_curvature: f32,
+    pub path_max_height: f32,
+    pub day_length: f32,
+    pub night_ambient: f32,
+    pub music_top_speed: f32,
+    pub daily_start_speed: f32,
+    pub daily_acceleration: f32,
+    pub daily_share_string: bool,
 }
 
 impl Default for GameConfig {
@@ -18,9 +46,46 @@ impl Default for GameConfig {
             speed_acceleration_rate: 0.2,
             current_speed: 15.0,
             barricade_spawn_base_interval: 8.0, // More space between obstacles
-            difficulty_scale: 1.0,
-            track_spacing: 2.0,
-            barricade_advance_time: 4.0, // Spawn further ahead
+            difficulty_distance: 150.0,         // Metres into a run for difficulty to rise a step
+            barricade_advance_time: 4.0,        // Spawn further ahead
+            hoverboard_duration: 30.0,
+            hoverboard_price: 50,
+            invulnerability_duration: 2.0, // Blink time after the board absorbs a hit
+            stumble_window: 6.0,           // A second stumble inside this window is fatal
+            stumble_slowdown: 0.6,         // Fraction of speed kept right after a stumble
+            stumble_recovery: 1.5,
+            chaser_gap: 2.5,
+            lane_switch_duration: 0.15, // Seconds to move one lane over
+            lane_switch_lean: 0.35,     // Peak body roll in radians
+            input_buffer_window: 0.15,  // How long a jump/slide press waits to become valid
+            coyote_time: 0.1,           // Grace to still jump after leaving a surface
+            fast_fall_speed: 20.0,      // Downward speed of the mid-air roll
+            landing_duration: 0.1,
+            slide_min_duration: 0.5,   // A tap slides this long
+            slide_max_duration: 1.5,   // Holding slide can't go past this (outside tunnels)
+            swipe_min_distance: 50.0,  // Pixels a touch or drag must travel to count as a swipe
+            swipe_min_velocity: 300.0, // Pixels per second; slower drags are ignored
+            tap_max_distance: 15.0,    // A touch that moves less than this is a tap
+            double_tap_window: 0.3,    // Max seconds between the two taps/jumps of a double-tap
+            train_reaction_time: 4.0, // Min seconds between a train spawning and reaching the player
+            train_warning_time: 3.0, // Horn and lane signal this many seconds before a train arrives
+            path_max_curvature: 0.004, // Tightest bend is a 250 m radius; 0.0 keeps the track straight
+            path_max_height: 6.0,      // Tallest hill or deepest dip; 0.0 keeps the track flat
+            day_length: 3000.0,        // Metres for a full day and night; 0.0 stops the clock
+            night_ambient: 0.3,        // Fraction of the ambient light left at midnight
+            music_top_speed: 40.0,     // Speed at which every music layer has faded in
+            daily_start_speed: 1.4,    // Base speed multiplier under the faster start modifier
+            daily_acceleration: 1.5, // Acceleration multiplier under the fast acceleration modifier
+            daily_share_string: true, // Show a result string to share after a daily run
         }
     }
 }
+
+impl GameConfig {
+    /// Difficulty at `z` metres into the run: 1.0 at the start, rising a step every
+    /// `difficulty_distance` up to 6.0. Spawners ask at the spot they fill, so the same
+    /// seed lays out the same run however fast it's played.
+    pub fn difficulty_at(&self, z: f32) -> f32 {
+        1.0 + (z.max(0.0) / self.difficulty_distance).min(5.0)
+    }
+}
@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+use std::ops::RangeInclusive;
+
+/// Environment variable that picks the number of lanes for a run
+const LANE_COUNT_ENV: &str = "SUBWAY_SURF_LANES";
+pub const MIN_LANES: u8 = 2;
+pub const MAX_LANES: u8 = 5;
+
+/// Stretch of track where only some lanes are open, such as a narrowing bridge
+#[derive(Clone, Debug)]
+pub struct LaneSection {
+    pub start_z: f32,
+    pub end_z: f32,
+    pub first_open: u8,
+    pub last_open: u8,
+}
+
+/// How many lanes there are, how far apart they sit, and which are closed where.
+///
+/// Lanes are numbered from 0 at the most negative x and centred on x = 0. Sections never
+/// move lanes, they only close some of them, so a lane keeps its x position for
+/// the whole run.
+#[derive(Resource)]
+pub struct LaneLayout {
+    pub count: u8,
+    pub spacing: f32,
+    sections: Vec<LaneSection>,
+}
+
+impl Default for LaneLayout {
+    fn default() -> Self {
+        let count = std::env::var(LANE_COUNT_ENV)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(3);
+        Self::new(count, 2.0)
+    }
+}
+
+impl LaneLayout {
+    pub fn new(count: u8, spacing: f32) -> Self {
+        Self {
+            count: count.clamp(MIN_LANES, MAX_LANES),
+            spacing,
+            sections: Vec::new(),
+        }
+    }
+
+    pub fn x_position(&self, lane: u8) -> f32 {
+        (lane as f32 - (self.count as f32 - 1.0) / 2.0) * self.spacing
+    }
+
+    /// Distance from the centre to the outer edge of the outermost lane
+    pub fn half_width(&self) -> f32 {
+        self.count as f32 * self.spacing / 2.0
+    }
+
+    /// Lane the player starts in; the left of the two middle lanes when the count is even
+    pub fn middle(&self) -> u8 {
+        (self.count - 1) / 2
+    }
+
+    pub fn lanes(&self) -> std::ops::Range<u8> {
+        0..self.count
+    }
+
+    pub fn open_lanes_at(&self, z: f32) -> RangeInclusive<u8> {
+        self.sections
+            .iter()
+            .find(|section| z >= section.start_z && z <= section.end_z)
+            .map_or(0..=self.count - 1, |section| {
+                section.first_open..=section.last_open
+            })
+    }
+
+    pub fn is_open(&self, lane: u8, z: f32) -> bool {
+        self.open_lanes_at(z).contains(&lane)
+    }
+
+    /// Whether `lane` stays open everywhere between `from_z` and `to_z`
+    pub fn is_open_between(&self, lane: u8, from_z: f32, to_z: f32) -> bool {
+        self.sections.iter().all(|section| {
+            section.end_z < from_z
+                || section.start_z > to_z
+                || (section.first_open..=section.last_open).contains(&lane)
+        })
+    }
+
+    /// Closest lane to `lane` that stays open between `from_z` and `to_z`
+    pub fn nearest_open_between(&self, lane: u8, from_z: f32, to_z: f32) -> Option<u8> {
+        self.lanes()
+            .filter(|&candidate| self.is_open_between(candidate, from_z, to_z))
+            .min_by_key(|&candidate| candidate.abs_diff(lane))
+    }
+
+    /// Picks an open lane at `z` from a random seed
+    pub fn random_open_lane(&self, seed: u32, z: f32) -> u8 {
+        let open = self.open_lanes_at(z);
+        let open_count = (open.end() - open.start() + 1) as u32;
+        open.start() + (seed % open_count) as u8
+    }
+
+    pub fn add_section(&mut self, section: LaneSection) {
+        self.sections.push(section);
+    }
+
+    pub fn sections(&self) -> &[LaneSection] {
+        &self.sections
+    }
+
+    /// Forgets sections that ended before `z`
+    pub fn prune_sections(&mut self, z: f32) {
+        self.sections.retain(|section| section.end_z >= z);
+    }
+
+    pub fn clear_sections(&mut self) {
+        self.sections.clear();
+    }
+}
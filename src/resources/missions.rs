@@ -0,0 +1,189 @@
+use bevy::prelude::*;
+use std::fs;
+
+/// Environment variable that overrides where missions are loaded from
+const MISSIONS_PATH_ENV: &str = "SUBWAY_SURF_MISSIONS";
+const DEFAULT_MISSIONS_PATH: &str = "assets/missions.txt";
+// Shipped missions, used when the mission file can't be read
+const BUILT_IN_MISSIONS: &str = include_str!("../../assets/missions.txt");
+/// Missions active at a time; finishing all of them completes a set
+pub const ACTIVE_MISSIONS: usize = 3;
+
+/// What a mission or achievement counts
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Goal {
+    Coins,
+    Jumps,
+    Slides,
+    LaneChanges,
+    NearMisses,
+    JumpOver,     // Barricades jumped over
+    SlideUnder,   // Barricades slid under
+    Tunnels,      // Low tunnels slid through
+    Distance,     // Metres run
+    RoofDistance, // Metres run on train roofs
+    Runs,
+}
+
+impl Goal {
+    pub const ALL: [Goal; 11] = [
+        Goal::Coins,
+        Goal::Jumps,
+        Goal::Slides,
+        Goal::LaneChanges,
+        Goal::NearMisses,
+        Goal::JumpOver,
+        Goal::SlideUnder,
+        Goal::Tunnels,
+        Goal::Distance,
+        Goal::RoofDistance,
+        Goal::Runs,
+    ];
+
+    /// Name used in the mission file and for the lifetime total in the profile
+    pub fn key(&self) -> &'static str {
+        match self {
+            Goal::Coins => "coins",
+            Goal::Jumps => "jumps",
+            Goal::Slides => "slides",
+            Goal::LaneChanges => "lane_changes",
+            Goal::NearMisses => "near_misses",
+            Goal::JumpOver => "jump_over",
+            Goal::SlideUnder => "slide_under",
+            Goal::Tunnels => "tunnels",
+            Goal::Distance => "distance",
+            Goal::RoofDistance => "roof_distance",
+            Goal::Runs => "runs",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Goal::ALL.into_iter().find(|goal| goal.key() == key)
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Whether progress has to be made within one run or may add up over several
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Scope {
+    Run,
+    Total,
+}
+
+/// A goal to reach, either a mission in the rotation or a one-time achievement
+#[derive(Clone, Debug)]
+pub struct Mission {
+    pub id: String,
+    pub name: String,
+    pub goal: Goal,
+    pub target: u32,
+    pub scope: Scope,
+}
+
+impl Mission {
+    fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: id.to_string(),
+            goal: Goal::Coins,
+            target: 1,
+            scope: Scope::Total,
+        }
+    }
+
+    fn apply_entry(&mut self, key: &str, value: &str) {
+        match key {
+            "name" => self.name = value.to_string(),
+            "goal" => {
+                if let Some(goal) = Goal::from_key(value) {
+                    self.goal = goal;
+                }
+            }
+            "target" => self.target = value.parse().unwrap_or(self.target).max(1),
+            "scope" => match value {
+                "run" => self.scope = Scope::Run,
+                "total" => self.scope = Scope::Total,
+                _ => {}
+            },
+            // Unknown keys are ignored so older builds can read newer mission files
+            _ => {}
+        }
+    }
+}
+
+/// Missions handed out in sets and achievements unlocked once, loaded from a plain text file
+#[derive(Resource)]
+pub struct MissionBook {
+    pub missions: Vec<Mission>,
+    pub achievements: Vec<Mission>,
+}
+
+impl Default for MissionBook {
+    fn default() -> Self {
+        let path =
+            std::env::var(MISSIONS_PATH_ENV).unwrap_or_else(|_| DEFAULT_MISSIONS_PATH.into());
+        let text = fs::read_to_string(&path).unwrap_or_else(|_| BUILT_IN_MISSIONS.to_string());
+        Self::parse(&text)
+    }
+}
+
+impl MissionBook {
+    /// Reads `[mission id]` and `[achievement id]` sections of `key = value` lines; lines
+    /// starting with `#` are comments
+    pub fn parse(text: &str) -> Self {
+        let mut book = Self {
+            missions: Vec::new(),
+            achievements: Vec::new(),
+        };
+        // Which list the section being read belongs to; `None` outside a known section
+        let mut in_achievements = None;
+        for line in text.lines() {
+            let line = line.trim();
+            if line.starts_with('#') {
+                continue;
+            }
+            if let Some(header) = line
+                .strip_prefix('[')
+                .and_then(|rest| rest.strip_suffix(']'))
+            {
+                in_achievements = match header.split_once(' ') {
+                    Some(("mission", id)) => {
+                        book.missions.push(Mission::new(id.trim()));
+                        Some(false)
+                    }
+                    Some(("achievement", id)) => {
+                        book.achievements.push(Mission::new(id.trim()));
+                        Some(true)
+                    }
+                    _ => None,
+                };
+            } else if let Some((key, value)) = line.split_once('=')
+                && let Some(achievement) = in_achievements
+            {
+                let list = if achievement {
+                    &mut book.achievements
+                } else {
+                    &mut book.missions
+                };
+                if let Some(mission) = list.last_mut() {
+                    mission.apply_entry(key.trim(), value.trim());
+                }
+            }
+        }
+        book
+    }
+
+    /// Missions of the set being played after `sets_completed` sets; the list loops
+    pub fn active(&self, sets_completed: u32) -> Vec<&Mission> {
+        if self.missions.is_empty() {
+            return Vec::new();
+        }
+        let first = sets_completed as usize * ACTIVE_MISSIONS;
+        (first..first + ACTIVE_MISSIONS.min(self.missions.len()))
+            .map(|index| &self.missions[index % self.missions.len()])
+            .collect()
+    }
+}
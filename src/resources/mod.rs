@@ -1,3 +1,11 @@
+pub mod daily;
+pub mod day_cycle;
+pub mod draw_horizon;
 pub mod game_config;
+pub mod lane_layout;
+pub mod missions;
+pub mod profile;
 pub mod score;
+pub mod theme;
+pub mod track_path;
 pub mod track_pool;
@@ -0,0 +1,224 @@
+use crate::resources::daily::Date;
+use crate::resources::missions::{ACTIVE_MISSIONS, Goal};
+use bevy::prelude::*;
+use std::fs;
+use std::path::PathBuf;
+
+/// Environment variable that overrides where the profile is stored
+const PROFILE_PATH_ENV: &str = "SUBWAY_SURF_PROFILE";
+const DEFAULT_PROFILE_PATH: &str = "profile.txt";
+// Profile keys holding input bindings, e.g. `bind.jump=Key:Space,Button:South`
+const BINDING_PREFIX: &str = "bind.";
+// Profile keys holding lifetime totals, e.g. `stat.jumps=412`
+const STAT_PREFIX: &str = "stat.";
+const DEFAULT_MASTER_VOLUME: f32 = 1.0;
+const DEFAULT_MUSIC_VOLUME: f32 = 0.7;
+const DEFAULT_SFX_VOLUME: f32 = 1.0;
+
+/// Progress that survives between runs and launches.
+///
+/// Stored as plain `key=value` lines so it can be inspected and edited by hand.
+#[derive(Resource)]
+pub struct PlayerProfile {
+    pub coins: u32,         // Banked coins, spendable in the shop
+    pub hoverboards: u32,   // Owned hoverboards
+    pub master_volume: f32, // Volume levels from 0 to 1; master scales the others
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub mission_sets: u32, // Completed mission sets; each adds one to the score multiplier
+    pub mission_progress: [f32; ACTIVE_MISSIONS], // Progress on each active mission
+    stats: [f32; Goal::ALL.len()], // Lifetime totals, indexed by `Goal::index`
+    achievements: Vec<String>, // Ids of unlocked achievements
+    daily_best: Option<(Date, u32)>, // Best daily run score, and the day it was set on
+    bindings: Vec<(String, String)>, // Saved input bindings, keyed by action
+    path: PathBuf,
+}
+
+impl Default for PlayerProfile {
+    fn default() -> Self {
+        let path = std::env::var(PROFILE_PATH_ENV)
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from(DEFAULT_PROFILE_PATH));
+        Self::load(path)
+    }
+}
+
+impl PlayerProfile {
+    /// Loads the profile at `path`, falling back to an empty profile if it is missing or unreadable
+    pub fn load(path: PathBuf) -> Self {
+        let mut profile = Self {
+            coins: 0,
+            hoverboards: 0,
+            master_volume: DEFAULT_MASTER_VOLUME,
+            music_volume: DEFAULT_MUSIC_VOLUME,
+            sfx_volume: DEFAULT_SFX_VOLUME,
+            mission_sets: 0,
+            mission_progress: [0.0; ACTIVE_MISSIONS],
+            stats: [0.0; Goal::ALL.len()],
+            achievements: Vec::new(),
+            daily_best: None,
+            bindings: Vec::new(),
+            path,
+        };
+
+        if let Ok(contents) = fs::read_to_string(&profile.path) {
+            for line in contents.lines() {
+                let Some((key, value)) = line.split_once('=') else {
+                    continue;
+                };
+                profile.apply_entry(key.trim(), value.trim());
+            }
+        }
+
+        profile
+    }
+
+    fn apply_entry(&mut self, key: &str, value: &str) {
+        match key {
+            "coins" => self.coins = value.parse().unwrap_or(0),
+            "hoverboards" => self.hoverboards = value.parse().unwrap_or(0),
+            "master_volume" => self.master_volume = parse_volume(value, DEFAULT_MASTER_VOLUME),
+            "music_volume" => self.music_volume = parse_volume(value, DEFAULT_MUSIC_VOLUME),
+            "sfx_volume" => self.sfx_volume = parse_volume(value, DEFAULT_SFX_VOLUME),
+            "mission_sets" => self.mission_sets = value.parse().unwrap_or(0),
+            "mission_progress" => {
+                for (slot, progress) in self.mission_progress.iter_mut().zip(value.split(',')) {
+                    *slot = progress.trim().parse().unwrap_or(0.0);
+                }
+            }
+            "achievements" => {
+                self.achievements = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|id| !id.is_empty())
+                    .map(str::to_string)
+                    .collect();
+            }
+            "daily_best" => {
+                self.daily_best = value.split_once(':').and_then(|(date, score)| {
+                    Some((Date::parse(date)?, score.trim().parse().ok()?))
+                });
+            }
+            _ => {
+                if let Some(action) = key.strip_prefix(BINDING_PREFIX) {
+                    self.set_binding(action, value.to_string());
+                } else if let Some(goal) = key.strip_prefix(STAT_PREFIX).and_then(Goal::from_key) {
+                    self.stats[goal.index()] = value.parse().unwrap_or(0.0);
+                }
+                // Unknown keys are ignored so older builds can read newer profiles
+            }
+        }
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = format!(
+            "coins={}\nhoverboards={}\nmaster_volume={}\nmusic_volume={}\nsfx_volume={}\n",
+            self.coins, self.hoverboards, self.master_volume, self.music_volume, self.sfx_volume
+        );
+        let progress: Vec<String> = self
+            .mission_progress
+            .iter()
+            .map(|progress| progress.to_string())
+            .collect();
+        text.push_str(&format!(
+            "mission_sets={}\nmission_progress={}\nachievements={}\n",
+            self.mission_sets,
+            progress.join(","),
+            self.achievements.join(",")
+        ));
+        if let Some((date, score)) = self.daily_best {
+            text.push_str(&format!("daily_best={date}:{score}\n"));
+        }
+        for goal in Goal::ALL {
+            text.push_str(&format!(
+                "{STAT_PREFIX}{}={}\n",
+                goal.key(),
+                self.stats[goal.index()]
+            ));
+        }
+        for (action, bindings) in &self.bindings {
+            text.push_str(&format!("{BINDING_PREFIX}{action}={bindings}\n"));
+        }
+        text
+    }
+
+    /// Saved bindings for `action`, as written by `ActionMap::save_to_profile`
+    pub fn binding(&self, action: &str) -> Option<&str> {
+        self.bindings
+            .iter()
+            .find(|(saved, _)| saved == action)
+            .map(|(_, bindings)| bindings.as_str())
+    }
+
+    pub fn set_binding(&mut self, action: &str, bindings: String) {
+        match self.bindings.iter_mut().find(|(saved, _)| saved == action) {
+            Some(entry) => entry.1 = bindings,
+            None => self.bindings.push((action.to_string(), bindings)),
+        }
+    }
+
+    /// Lifetime total counted towards `goal`
+    pub fn stat(&self, goal: Goal) -> f32 {
+        self.stats[goal.index()]
+    }
+
+    pub fn add_stat(&mut self, goal: Goal, amount: f32) {
+        self.stats[goal.index()] += amount;
+    }
+
+    pub fn has_achievement(&self, id: &str) -> bool {
+        self.achievements.iter().any(|unlocked| unlocked == id)
+    }
+
+    pub fn unlock_achievement(&mut self, id: &str) {
+        if !self.has_achievement(id) {
+            self.achievements.push(id.to_string());
+        }
+    }
+
+    /// Permanent score multiplier earned by completing mission sets
+    pub fn multiplier(&self) -> u32 {
+        1 + self.mission_sets
+    }
+
+    /// Best daily run score on `date`, or 0 if it hasn't been played
+    pub fn daily_best(&self, date: Date) -> u32 {
+        self.daily_best
+            .filter(|(best_date, _)| *best_date == date)
+            .map_or(0, |(_, score)| score)
+    }
+
+    /// Keeps `score` if it beats the best for `date`; an earlier day's best is replaced
+    pub fn record_daily_score(&mut self, date: Date, score: u32) -> bool {
+        if self
+            .daily_best
+            .is_some_and(|(best_date, best)| best_date == date && best >= score)
+        {
+            return false;
+        }
+        self.daily_best = Some((date, score));
+        true
+    }
+
+    pub fn save(&self) {
+        if let Err(error) = fs::write(&self.path, self.to_text()) {
+            warn!("Failed to save profile to {}: {error}", self.path.display());
+        }
+    }
+
+    /// Spends `amount` banked coins if the balance allows it
+    pub fn try_spend(&mut self, amount: u32) -> bool {
+        if self.coins < amount {
+            return false;
+        }
+        self.coins -= amount;
+        true
+    }
+}
+
+/// Reads a saved volume level, keeping it within 0 to 1
+fn parse_volume(value: &str, default: f32) -> f32 {
+    value
+        .parse::<f32>()
+        .map_or(default, |level| level.clamp(0.0, 1.0))
+}
@@ -1,12 +1,22 @@
 use bevy::prelude::*;
 
+// Points a coin is worth, on top of a point per metre run
+const COIN_POINTS: u32 = 10;
+
 #[derive(Resource, Default)]
 pub struct Score {
     pub coins: u32,
+    pub distance: f32, // Furthest the player has got this run, in metres
 }
 
 impl Score {
     pub fn reset(&mut self) {
         self.coins = 0;
+        self.distance = 0.0;
+    }
+
+    /// Score for the run so far, scaled by the multiplier earned from missions
+    pub fn points(&self, multiplier: u32) -> u32 {
+        (self.distance as u32 + self.coins * COIN_POINTS) * multiplier
     }
 }
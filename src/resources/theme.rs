@@ -0,0 +1,331 @@
+use bevy::prelude::*;
+use std::fs;
+
+/// Environment variable that overrides where themes are loaded from
+const THEMES_PATH_ENV: &str = "SUBWAY_SURF_THEMES";
+const DEFAULT_THEMES_PATH: &str = "assets/themes.txt";
+// Shipped themes, used when the theme file can't be read
+const BUILT_IN_THEMES: &str = include_str!("../../assets/themes.txt");
+/// Length of the section in which one theme blends into the next
+pub const TRANSITION_LENGTH: f32 = 150.0;
+
+/// Relative chances of each trackside prop
+#[derive(Clone, Copy)]
+pub struct PropWeights {
+    pub lamp_post: u32,
+    pub pillar: u32,
+    pub fence: u32,
+    pub sign: u32,
+}
+
+/// Key and pace of the music generated for a theme
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MusicStyle {
+    pub root: f32,  // Frequency of the key's bass note, in Hz
+    pub tempo: f32, // Beats per minute
+    pub minor: bool,
+}
+
+/// Look and sound of one stretch of the run: palette, props, sky and light
+#[derive(Clone)]
+pub struct Theme {
+    pub name: String,
+    pub length: f32, // Metres before the transition into the next theme
+    pub sky: Color,
+    pub sun_color: Color,
+    pub sun_illuminance: f32,
+    pub ambient_color: Color,
+    pub ambient_brightness: f32,
+    pub rail: Color,
+    pub sleeper: Color,
+    pub train_body: Color,
+    pub express_body: Color,
+    pub train_stripe: Color,
+    pub train_roof: Color,
+    pub train_window: Color,
+    pub barricade: Color,
+    pub barricade_trim: Color,
+    pub warning_light: Color,
+    pub tunnel: Color,
+    pub buildings: Vec<Color>,
+    pub window_light: Color,
+    pub signs: Vec<Color>,
+    pub fence: Color,
+    pub lamp_light: Color,
+    pub props: PropWeights,
+    pub floors: (u32, u32), // Fewest and most building floors
+    pub music: MusicStyle,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            name: "subway".to_string(),
+            length: 1200.0,
+            sky: Color::srgb(0.55, 0.65, 0.75),
+            sun_color: Color::WHITE,
+            sun_illuminance: 10000.0,
+            ambient_color: Color::WHITE,
+            ambient_brightness: 500.0,
+            rail: Color::srgb(0.4, 0.4, 0.45),
+            sleeper: Color::srgb(0.35, 0.2, 0.1),
+            train_body: Color::srgb(0.7, 0.1, 0.15),
+            express_body: Color::srgb(0.75, 0.78, 0.82),
+            train_stripe: Color::srgb(0.95, 0.85, 0.2),
+            train_roof: Color::srgb(0.5, 0.5, 0.55),
+            train_window: Color::srgb(0.2, 0.3, 0.4),
+            barricade: Color::srgb(0.9, 0.2, 0.15),
+            barricade_trim: Color::srgb(0.95, 0.9, 0.75),
+            warning_light: Color::srgb(1.0, 0.85, 0.1),
+            tunnel: Color::srgb(0.55, 0.55, 0.5),
+            buildings: vec![
+                Color::srgb(0.55, 0.5, 0.48),
+                Color::srgb(0.6, 0.35, 0.28),
+                Color::srgb(0.4, 0.45, 0.55),
+                Color::srgb(0.7, 0.65, 0.55),
+                Color::srgb(0.35, 0.38, 0.4),
+            ],
+            window_light: Color::srgb(1.0, 0.85, 0.5),
+            signs: vec![
+                Color::srgb(0.8, 0.2, 0.2),
+                Color::srgb(0.15, 0.4, 0.8),
+                Color::srgb(0.95, 0.75, 0.1),
+            ],
+            fence: Color::srgb(0.3, 0.45, 0.3),
+            lamp_light: Color::srgb(1.0, 0.95, 0.8),
+            props: PropWeights {
+                lamp_post: 4,
+                pillar: 2,
+                fence: 3,
+                sign: 1,
+            },
+            floors: (2, 8),
+            music: MusicStyle {
+                root: 55.0,
+                tempo: 120.0,
+                minor: true,
+            },
+        }
+    }
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    Srgba::hex(value.trim()).ok().map(Color::from)
+}
+
+fn parse_colors(value: &str) -> Option<Vec<Color>> {
+    let colors: Vec<Color> = value.split(',').filter_map(parse_color).collect();
+    (!colors.is_empty()).then_some(colors)
+}
+
+impl Theme {
+    fn apply_entry(&mut self, key: &str, value: &str) {
+        let color = parse_color(value);
+        let number = value.parse::<f32>().ok();
+        match key {
+            "length" => self.length = number.unwrap_or(self.length).max(0.0),
+            "sun_illuminance" => self.sun_illuminance = number.unwrap_or(self.sun_illuminance),
+            "ambient_brightness" => {
+                self.ambient_brightness = number.unwrap_or(self.ambient_brightness)
+            }
+            "music_root" => self.music.root = number.unwrap_or(self.music.root).max(20.0),
+            "music_tempo" => self.music.tempo = number.unwrap_or(self.music.tempo).max(30.0),
+            "music_scale" => match value {
+                "major" => self.music.minor = false,
+                "minor" => self.music.minor = true,
+                _ => {}
+            },
+            "buildings" => {
+                if let Some(colors) = parse_colors(value) {
+                    self.buildings = colors;
+                }
+            }
+            "signs" => {
+                if let Some(colors) = parse_colors(value) {
+                    self.signs = colors;
+                }
+            }
+            "props" => {
+                for entry in value.split(',') {
+                    let Some((prop, weight)) = entry.split_once(':') else {
+                        continue;
+                    };
+                    let Ok(weight) = weight.trim().parse() else {
+                        continue;
+                    };
+                    match prop.trim() {
+                        "lamp_post" => self.props.lamp_post = weight,
+                        "pillar" => self.props.pillar = weight,
+                        "fence" => self.props.fence = weight,
+                        "sign" => self.props.sign = weight,
+                        _ => {}
+                    }
+                }
+            }
+            "floors" => {
+                if let Some((fewest, most)) = value.split_once('-')
+                    && let (Ok(fewest), Ok(most)) = (fewest.trim().parse(), most.trim().parse())
+                {
+                    self.floors = (fewest.max(1), most.max(fewest.max(1)));
+                }
+            }
+            _ => {
+                let Some(color) = color else {
+                    return;
+                };
+                let slot = match key {
+                    "sky" => &mut self.sky,
+                    "sun_color" => &mut self.sun_color,
+                    "ambient_color" => &mut self.ambient_color,
+                    "rail" => &mut self.rail,
+                    "sleeper" => &mut self.sleeper,
+                    "train_body" => &mut self.train_body,
+                    "express_body" => &mut self.express_body,
+                    "train_stripe" => &mut self.train_stripe,
+                    "train_roof" => &mut self.train_roof,
+                    "train_window" => &mut self.train_window,
+                    "barricade" => &mut self.barricade,
+                    "barricade_trim" => &mut self.barricade_trim,
+                    "warning_light" => &mut self.warning_light,
+                    "tunnel" => &mut self.tunnel,
+                    "window_light" => &mut self.window_light,
+                    "fence" => &mut self.fence,
+                    "lamp_light" => &mut self.lamp_light,
+                    // Unknown keys are ignored so older builds can read newer theme files
+                    _ => return,
+                };
+                *slot = color;
+            }
+        }
+    }
+
+    /// Mixes towards `other` by `t`; lists, counts and music switch over halfway
+    fn blend(&self, other: &Theme, t: f32) -> Theme {
+        let mix = |a: Color, b: Color| a.mix(&b, t);
+        // As many colours as the dominant theme has, each mixed with its counterpart
+        let mix_list = |a: &[Color], b: &[Color], count: usize| -> Vec<Color> {
+            (0..count)
+                .map(|i| a[i % a.len()].mix(&b[i % b.len()], t))
+                .collect()
+        };
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        let dominant = if t < 0.5 { self } else { other };
+
+        Theme {
+            name: dominant.name.clone(),
+            length: dominant.length,
+            sky: mix(self.sky, other.sky),
+            sun_color: mix(self.sun_color, other.sun_color),
+            sun_illuminance: lerp(self.sun_illuminance, other.sun_illuminance),
+            ambient_color: mix(self.ambient_color, other.ambient_color),
+            ambient_brightness: lerp(self.ambient_brightness, other.ambient_brightness),
+            rail: mix(self.rail, other.rail),
+            sleeper: mix(self.sleeper, other.sleeper),
+            train_body: mix(self.train_body, other.train_body),
+            express_body: mix(self.express_body, other.express_body),
+            train_stripe: mix(self.train_stripe, other.train_stripe),
+            train_roof: mix(self.train_roof, other.train_roof),
+            train_window: mix(self.train_window, other.train_window),
+            barricade: mix(self.barricade, other.barricade),
+            barricade_trim: mix(self.barricade_trim, other.barricade_trim),
+            warning_light: mix(self.warning_light, other.warning_light),
+            tunnel: mix(self.tunnel, other.tunnel),
+            buildings: mix_list(&self.buildings, &other.buildings, dominant.buildings.len()),
+            window_light: mix(self.window_light, other.window_light),
+            signs: mix_list(&self.signs, &other.signs, dominant.signs.len()),
+            fence: mix(self.fence, other.fence),
+            lamp_light: mix(self.lamp_light, other.lamp_light),
+            props: dominant.props,
+            floors: dominant.floors,
+            music: dominant.music,
+        }
+    }
+}
+
+/// The themes a run passes through, loaded from a plain text file.
+///
+/// Each theme lasts its `length`, then a `TRANSITION_LENGTH` section blends it into
+/// the next; after the last theme the run loops back to the first.
+#[derive(Resource)]
+pub struct Themes {
+    themes: Vec<Theme>,
+}
+
+impl Default for Themes {
+    fn default() -> Self {
+        let path = std::env::var(THEMES_PATH_ENV).unwrap_or_else(|_| DEFAULT_THEMES_PATH.into());
+        let text = fs::read_to_string(&path).unwrap_or_else(|_| BUILT_IN_THEMES.to_string());
+        Self::parse(&text)
+    }
+}
+
+impl Themes {
+    /// Reads `[name]` sections of `key = value` lines; lines starting with `#` are comments
+    pub fn parse(text: &str) -> Self {
+        let mut themes: Vec<Theme> = Vec::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|rest| rest.strip_suffix(']'))
+            {
+                themes.push(Theme {
+                    name: name.trim().to_string(),
+                    ..default()
+                });
+            } else if let Some((key, value)) = line.split_once('=')
+                && let Some(theme) = themes.last_mut()
+            {
+                theme.apply_entry(key.trim(), value.trim());
+            }
+        }
+
+        if themes.is_empty() {
+            themes.push(Theme::default());
+        }
+        Self { themes }
+    }
+
+    fn cycle_length(&self) -> f32 {
+        self.themes
+            .iter()
+            .map(|theme| theme.length + TRANSITION_LENGTH)
+            .sum()
+    }
+
+    /// Theme in effect at distance `z`, blended if `z` is in a transition section
+    pub fn at(&self, z: f32) -> Theme {
+        let mut remaining = z.max(0.0) % self.cycle_length();
+        for (index, theme) in self.themes.iter().enumerate() {
+            if remaining < theme.length {
+                return theme.clone();
+            }
+            remaining -= theme.length;
+            if remaining < TRANSITION_LENGTH {
+                let next = &self.themes[(index + 1) % self.themes.len()];
+                let t = remaining / TRANSITION_LENGTH;
+                return theme.blend(next, t * t * (3.0 - 2.0 * t));
+            }
+            remaining -= TRANSITION_LENGTH;
+        }
+        self.themes[0].clone()
+    }
+
+    /// Start of the first transition section that begins after `z`
+    pub fn next_transition_after(&self, z: f32) -> f32 {
+        let cycle = self.cycle_length();
+        let mut start = (z.max(0.0) / cycle).floor() * cycle;
+        loop {
+            for theme in &self.themes {
+                start += theme.length;
+                if start > z {
+                    return start;
+                }
+                start += TRANSITION_LENGTH;
+            }
+        }
+    }
+}
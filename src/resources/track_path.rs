@@ -0,0 +1,175 @@
+use crate::resources::game_config::GameConfig;
+use bevy::prelude::*;
+
+// Distance between integrated samples along the path
+const PATH_STEP: f32 = 1.0;
+// Distance between the random bends and hills the path eases through
+const CONTROL_SPACING: f32 = 80.0;
+// Leading controls kept straight and flat so a run starts on a level line
+const STRAIGHT_CONTROLS: u32 = 2;
+
+/// Spacing of the grid that seeded spawns are placed on
+pub const SPAWN_GRID: f32 = 2.0;
+
+/// Moves `z` up onto the spawn grid, so where the player happened to be on the frame a
+/// spawn was placed doesn't shift it or change what it rolls
+pub fn snap_to_grid(z: f32) -> f32 {
+    (z / SPAWN_GRID).ceil() * SPAWN_GRID
+}
+
+/// Position and orientation of the track centre line at some distance
+#[derive(Clone, Copy)]
+pub struct PathFrame {
+    pub origin: Vec3,
+    pub rotation: Quat,
+}
+
+#[derive(Clone, Copy)]
+struct PathSample {
+    origin: Vec3,
+    heading: f32,
+    pitch: f32,
+}
+
+/// The winding, rolling line the track follows through the world.
+///
+/// Gameplay runs in straight track space, where z is the distance along the path,
+/// x the sideways offset from its centre and y the height above it. This maps those
+/// coordinates onto the path, which is integrated from seeded bends and hills a
+/// sample at a time as the run goes on.
+#[derive(Resource)]
+pub struct TrackPath {
+    seed: u32,
+    samples: Vec<PathSample>,
+}
+
+impl Default for TrackPath {
+    fn default() -> Self {
+        Self::new(0x7A7C_5EED)
+    }
+}
+
+// Simple pseudo-random for path controls, in -1.0..=1.0
+fn path_random(seed: u32) -> f32 {
+    let mut x = seed;
+    x ^= x << 13;
+    x ^= x >> 17;
+    x ^= x << 5;
+    (x % 2001) as f32 / 1000.0 - 1.0
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+impl TrackPath {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            seed,
+            samples: vec![PathSample {
+                origin: Vec3::ZERO,
+                heading: 0.0,
+                pitch: 0.0,
+            }],
+        }
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// Key for seeding whatever spawns at grid spot `z`, mixing the track seed with a
+    /// `salt` for each kind of spawn
+    pub fn spawn_key(&self, salt: u32, z: f32) -> u32 {
+        let slot = (z / SPAWN_GRID).round() as u32;
+        self.seed ^ salt ^ slot.wrapping_mul(0x9E37_79B9)
+    }
+
+    /// Curvature and height at a control point
+    fn control(&self, index: u32, game_config: &GameConfig) -> (f32, f32) {
+        if index < STRAIGHT_CONTROLS {
+            return (0.0, 0.0);
+        }
+        let key = self.seed ^ index.wrapping_mul(0x9E37_79B9);
+        (
+            path_random(key) * game_config.path_max_curvature,
+            path_random(key ^ 0x0000_B111) * game_config.path_max_height,
+        )
+    }
+
+    /// Curvature, height and slope at `distance`, eased between the controls either side
+    fn shape_at(&self, distance: f32, game_config: &GameConfig) -> (f32, f32, f32) {
+        let position = distance.max(0.0) / CONTROL_SPACING;
+        let index = position.floor() as u32;
+        let t = position.fract();
+        let (curvature_a, height_a) = self.control(index, game_config);
+        let (curvature_b, height_b) = self.control(index + 1, game_config);
+
+        let eased = smoothstep(t);
+        let slope = (height_b - height_a) * 6.0 * t * (1.0 - t) / CONTROL_SPACING;
+        (
+            curvature_a + (curvature_b - curvature_a) * eased,
+            height_a + (height_b - height_a) * eased,
+            slope,
+        )
+    }
+
+    /// Integrates the path out to at least `distance`
+    pub fn extend_to(&mut self, distance: f32, game_config: &GameConfig) {
+        while (self.samples.len() - 1) as f32 * PATH_STEP < distance {
+            let last = self.samples[self.samples.len() - 1];
+            let start = (self.samples.len() - 1) as f32 * PATH_STEP;
+            let (curvature, _, _) = self.shape_at(start + PATH_STEP / 2.0, game_config);
+            let mid_heading = last.heading + curvature * PATH_STEP / 2.0;
+            let (_, height, slope) = self.shape_at(start + PATH_STEP, game_config);
+
+            self.samples.push(PathSample {
+                origin: Vec3::new(
+                    last.origin.x + mid_heading.sin() * PATH_STEP,
+                    height,
+                    last.origin.z + mid_heading.cos() * PATH_STEP,
+                ),
+                heading: last.heading + curvature * PATH_STEP,
+                pitch: slope.atan(),
+            });
+        }
+    }
+
+    /// Frame of the centre line at `distance`; runs straight on past either end
+    pub fn frame_at(&self, distance: f32) -> PathFrame {
+        let last_index = self.samples.len() - 1;
+        let position = distance / PATH_STEP;
+        let index = (position.floor().max(0.0) as usize).min(last_index);
+        let next = (index + 1).min(last_index);
+        let a = self.samples[index];
+        let b = self.samples[next];
+
+        let t = if next == index {
+            0.0
+        } else {
+            (position - index as f32).clamp(0.0, 1.0)
+        };
+        let heading = a.heading + (b.heading - a.heading) * t;
+        let pitch = a.pitch + (b.pitch - a.pitch) * t;
+        let rotation = Quat::from_rotation_y(heading) * Quat::from_rotation_x(-pitch);
+
+        // Beyond the sampled stretch, carry on along the end tangent
+        let overshoot = (position - index as f32 - t) * PATH_STEP;
+        PathFrame {
+            origin: a.origin.lerp(b.origin, t) + rotation * Vec3::Z * overshoot,
+            rotation,
+        }
+    }
+
+    /// Maps a whole transform from track space onto the path, keeping its scale
+    pub fn bend(&self, transform: &GlobalTransform) -> GlobalTransform {
+        let (scale, rotation, translation) = transform.to_scale_rotation_translation();
+        let frame = self.frame_at(translation.z);
+        GlobalTransform::from(Transform {
+            translation: frame.origin
+                + frame.rotation * Vec3::new(translation.x, translation.y, 0.0),
+            rotation: frame.rotation * rotation,
+            scale,
+        })
+    }
+}